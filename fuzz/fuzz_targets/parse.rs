@@ -0,0 +1,15 @@
+//! Fuzz target feeding arbitrary bytes through the parser and SVG converter.
+//!
+//! Malformed input must only ever produce an `Err`, never a panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wvg::{BitStream, Converter, SvgConverter, WvgParser};
+
+fuzz_target!(|data: &[u8]| {
+    let mut bs = BitStream::new(data);
+    if let Ok(document) = WvgParser::new(&mut bs).parse() {
+        let _ = SvgConverter::new().convert(&document);
+    }
+});
@@ -19,6 +19,26 @@ pub struct BitStream<'a> {
     bit_pos: u8,
 }
 
+/// A saved position within a `BitStream`.
+///
+/// Obtained from `BitStream::checkpoint` and passed to `BitStream::restore`
+/// to rewind the stream. A position only has meaning for the stream that
+/// produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitPos {
+    /// Byte position at the time of the checkpoint
+    byte_pos: usize,
+    /// Bit position within the byte at the time of the checkpoint
+    bit_pos: u8,
+}
+
+impl BitPos {
+    /// Returns the position as a number of bits from the start of the stream.
+    pub fn bit_offset(&self) -> usize {
+        self.byte_pos * 8 + self.bit_pos as usize
+    }
+}
+
 impl<'a> BitStream<'a> {
     /// Creates a new BitStream from the given byte slice.
     ///
@@ -66,15 +86,17 @@ impl<'a> BitStream<'a> {
     ///
     /// # Arguments
     ///
-    /// * `n` - The number of bits to read (1-32)
+    /// * `n` - The number of bits to read (0-32)
     ///
     /// # Returns
     ///
-    /// The unsigned integer value formed by the read bits.
+    /// The unsigned integer value formed by the read bits. Reading zero bits
+    /// yields 0 and never fails, even on an empty or exhausted stream, since
+    /// a zero-width field occupies no data.
     ///
     /// # Errors
     ///
-    /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.    
+    /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.
     pub fn read_bits(&mut self, n: u8) -> WvgResult<u32> {
         let mut val: u32 = 0;
         for _ in 0..n {
@@ -83,35 +105,137 @@ impl<'a> BitStream<'a> {
         Ok(val)
     }
 
+    /// Reads `n` bits (0-8) from the stream as a `u8`.
+    ///
+    /// Like `read_bits`, but returns the narrow type directly. Reading more
+    /// than 8 bits is a bug in the caller and panics in debug builds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.
+    pub fn read_bits_u8(&mut self, n: u8) -> WvgResult<u8> {
+        debug_assert!(n <= 8, "cannot read {} bits into a u8", n);
+        Ok(self.read_bits(n)? as u8)
+    }
+
+    /// Reads `n` bits (0-16) from the stream as a `u16`.
+    ///
+    /// Like `read_bits`, but returns the narrow type directly. Reading more
+    /// than 16 bits is a bug in the caller and panics in debug builds.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.
+    pub fn read_bits_u16(&mut self, n: u8) -> WvgResult<u16> {
+        debug_assert!(n <= 16, "cannot read {} bits into a u16", n);
+        Ok(self.read_bits(n)? as u16)
+    }
+
     /// Reads `n` bits from the stream as a signed integer using two's complement.
     ///
     /// # Arguments
     ///
-    /// * `n` - The number of bits to read (1-32)
+    /// * `n` - The number of bits to read (0-32)
     ///
     /// # Returns
     ///
     /// The signed integer value using two's complement representation.
+    /// Reading zero bits yields 0. A negative value is the raw bits minus
+    /// `2^n`, computed in 64 bits so it cannot overflow; for `n == 32` this
+    /// is the same as reinterpreting the bits as an `i32`.
     ///
     /// # Errors
     ///
     /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.
     pub fn read_signed_bits(&mut self, n: u8) -> WvgResult<i32> {
+        if n == 0 {
+            return Ok(0);
+        }
+
         let val = self.read_bits(n)?;
+        if n == 32 {
+            return Ok(val as i32);
+        }
         // Check if the sign bit (MSB of the n bits) is set
         if val & (1 << (n - 1)) != 0 {
             // Sign extend by subtracting 2^n
-            Ok(val as i32 - (1 << n))
+            Ok((i64::from(val) - (1i64 << n)) as i32)
         } else {
             Ok(val as i32)
         }
     }
 
+    /// Reads `n` bits from the stream as a signed integer in sign-magnitude form.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of bits to read (0-32), including the sign bit
+    ///
+    /// # Returns
+    ///
+    /// The magnitude from the low `n - 1` bits, negated if the leading sign
+    /// bit is set. Reading zero bits yields 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WvgError::EndOfStream` if attempting to read past the end of data.
+    pub fn read_sign_magnitude_bits(&mut self, n: u8) -> WvgResult<i32> {
+        if n == 0 {
+            return Ok(0);
+        }
+
+        let negative = self.read_bit()? == 1;
+        let magnitude = i64::from(self.read_bits(n - 1)?);
+        Ok(if negative { -magnitude } else { magnitude } as i32)
+    }
+
     /// Returns true if more bits are available.
     pub fn has_more_bits(&self) -> bool {
         self.byte_pos < self.data.len()
     }
 
+    /// Returns the number of unread bits.
+    pub fn remaining_bits(&self) -> usize {
+        (self.data.len() - self.byte_pos) * 8 - self.bit_pos as usize
+    }
+
+    /// Returns true if only zero bits remain in the current byte.
+    ///
+    /// Encoders pad the final byte with zeros, so this indicates that the
+    /// meaningful data has been fully consumed.
+    pub fn only_padding_remains(&self) -> bool {
+        if self.byte_pos + 1 < self.data.len() {
+            return false;
+        }
+        match self.data.get(self.byte_pos) {
+            Some(&byte) => byte & (0xFF >> self.bit_pos) == 0,
+            None => true,
+        }
+    }
+
+    /// Returns the current position so it can later be restored.
+    pub fn checkpoint(&self) -> BitPos {
+        BitPos {
+            byte_pos: self.byte_pos,
+            bit_pos: self.bit_pos,
+        }
+    }
+
+    /// Rewinds (or advances) the stream to a previously saved position.
+    ///
+    /// `pos` should come from `checkpoint` on this same stream. A position
+    /// past the end, e.g. one taken from a longer stream, is clamped to the
+    /// end of the data, so later reads fail with `EndOfStream`.
+    pub fn restore(&mut self, pos: BitPos) {
+        if pos.byte_pos >= self.data.len() {
+            self.byte_pos = self.data.len();
+            self.bit_pos = 0;
+        } else {
+            self.byte_pos = pos.byte_pos;
+            self.bit_pos = pos.bit_pos;
+        }
+    }
+
     /// Returns the current byte position in the stream.
     pub fn byte_position(&self) -> usize {
         self.byte_pos
@@ -162,6 +286,33 @@ mod tests {
         assert_eq!(bs.read_bits(4).unwrap(), 0b1111);
     }
 
+    #[test]
+    fn test_read_bits_narrow() {
+        let data = vec![0b10110011, 0b11000011, 0b10100000];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_bits_u8(8).unwrap(), 0b10110011);
+        assert_eq!(bs.read_bits_u16(16).unwrap(), 0b11000011_10100000);
+        assert_eq!(bs.read_bits_u8(0).unwrap(), 0);
+        assert!(matches!(bs.read_bits_u8(1), Err(WvgError::EndOfStream)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "cannot read 9 bits into a u8")]
+    fn test_read_bits_u8_too_wide() {
+        let data = vec![0xff, 0xff];
+        let _ = BitStream::new(&data).read_bits_u8(9);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "cannot read 17 bits into a u16")]
+    fn test_read_bits_u16_too_wide() {
+        let data = vec![0xff, 0xff, 0xff];
+        let _ = BitStream::new(&data).read_bits_u16(17);
+    }
+
     #[test]
     fn test_read_signed_bits_positive() {
         let data = vec![0b01111111]; // 127 in 8-bit signed
@@ -186,6 +337,51 @@ mod tests {
         assert_eq!(bs.read_signed_bits(3).unwrap(), -1);
     }
 
+    #[test]
+    fn test_read_signed_bits_full_width() {
+        let data = vec![0x7F, 0xFF, 0xFF, 0xFF, 0x80, 0x00, 0x00, 0x01];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_signed_bits(32).unwrap(), i32::MAX);
+        assert_eq!(bs.read_signed_bits(32).unwrap(), i32::MIN + 1);
+    }
+
+    #[test]
+    fn test_read_signed_bits_zero_width() {
+        let data = vec![0xFF];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_signed_bits(0).unwrap(), 0);
+        assert_eq!(bs.bit_position(), 0);
+    }
+
+    #[test]
+    fn test_read_sign_magnitude_bits() {
+        let data = vec![0b10110011]; // 1011 = -3, 0011 = 3
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_sign_magnitude_bits(4).unwrap(), -3);
+        assert_eq!(bs.read_sign_magnitude_bits(4).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_read_sign_magnitude_negative_zero() {
+        let data = vec![0b10000000];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_sign_magnitude_bits(3).unwrap(), 0);
+        assert_eq!(bs.read_sign_magnitude_bits(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_zero_width_read_on_empty_stream() {
+        let mut bs = BitStream::new(&[]);
+
+        assert_eq!(bs.read_bits(0).unwrap(), 0);
+        assert_eq!(bs.read_signed_bits(0).unwrap(), 0);
+        assert!(matches!(bs.read_bit(), Err(WvgError::EndOfStream)));
+    }
+
     #[test]
     fn test_end_of_stream() {
         let data = vec![0xFF];
@@ -215,6 +411,45 @@ mod tests {
         assert!(!bs.has_more_bits());
     }
 
+    #[test]
+    fn test_remaining_bits() {
+        let data = vec![0xFF, 0xFF];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.remaining_bits(), 16);
+        bs.read_bits(5).unwrap();
+        assert_eq!(bs.remaining_bits(), 11);
+        bs.read_bits(11).unwrap();
+        assert_eq!(bs.remaining_bits(), 0);
+    }
+
+    #[test]
+    fn test_only_padding_remains() {
+        let data = vec![0xFF, 0b10100000];
+        let mut bs = BitStream::new(&data);
+
+        assert!(!bs.only_padding_remains());
+        bs.read_bits(10).unwrap();
+        assert!(!bs.only_padding_remains());
+        bs.read_bit().unwrap();
+        assert!(bs.only_padding_remains());
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let data = vec![0b10110011, 0b01011100];
+        let mut bs = BitStream::new(&data);
+
+        assert_eq!(bs.read_bits(3).unwrap(), 0b101);
+        let pos = bs.checkpoint();
+        assert_eq!(pos.bit_offset(), 3);
+
+        assert_eq!(bs.read_bits(9).unwrap(), 0b100110101);
+        bs.restore(pos);
+        assert_eq!(bs.checkpoint(), pos);
+        assert_eq!(bs.read_bits(9).unwrap(), 0b100110101);
+    }
+
     #[test]
     fn test_cross_byte_boundary() {
         let data = vec![0b11110000, 0b11110000];
@@ -225,4 +460,19 @@ mod tests {
         // Read 6 bits crossing byte boundary
         assert_eq!(bs.read_bits(6).unwrap(), 0b001111);
     }
+
+    #[test]
+    fn test_restore_foreign_position_clamps() {
+        let long = [0u8; 4];
+        let mut other = BitStream::new(&long);
+        other.read_bits(27).unwrap();
+        let pos = other.checkpoint();
+
+        let short = [0xFF];
+        let mut bs = BitStream::new(&short);
+        bs.restore(pos);
+        assert_eq!(bs.remaining_bits(), 0);
+        assert!(!bs.has_more_bits());
+        assert!(matches!(bs.read_bit(), Err(WvgError::EndOfStream)));
+    }
 }
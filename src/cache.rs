@@ -0,0 +1,341 @@
+//! Memoizing converter wrapper.
+//!
+//! This module provides `CachedConverter`, which wraps any `Converter` and
+//! reuses previous outputs for identical documents.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use crate::converter::Converter;
+use crate::error::WvgResult;
+use crate::types::WvgDocument;
+use crate::log::trace;
+
+/// Converter that caches the outputs of an inner converter.
+///
+/// Documents are looked up by their 64-bit hash and compared in full, so a
+/// hash collision never returns another document's output. The least
+/// recently used entry is evicted once the cache is full. Failed conversions
+/// are not cached.
+///
+/// # Example
+///
+/// ```ignore
+/// use wvg::{CachedConverter, Converter, SvgConverter};
+///
+/// let converter = CachedConverter::new(SvgConverter::new(), 128);
+/// let svg = converter.convert(&document)?;
+/// let again = converter.convert(&document)?; // served from the cache
+/// ```
+pub struct CachedConverter<C: Converter> {
+    /// The wrapped converter.
+    inner: C,
+    /// Maximum number of cached outputs.
+    capacity: usize,
+    /// Cached outputs and their recency order.
+    cache: Mutex<LruCache<C::Output>>,
+}
+
+/// Marks the absence of a neighbouring slot in the recency list.
+const NIL: usize = usize::MAX;
+
+/// A cached output together with the document it was converted from.
+struct Entry<T> {
+    hash: u64,
+    document: WvgDocument,
+    output: T,
+    /// Next less recently used slot.
+    prev: usize,
+    /// Next more recently used slot.
+    next: usize,
+}
+
+/// Cached outputs in a slab, linked from least to most recently used.
+///
+/// `index` maps a document hash to the slots holding documents with that
+/// hash, so lookups, promotions and evictions are all O(1).
+struct LruCache<T> {
+    index: HashMap<u64, Vec<usize>>,
+    slots: Vec<Entry<T>>,
+    /// Least recently used slot.
+    head: usize,
+    /// Most recently used slot.
+    tail: usize,
+}
+
+impl<T> LruCache<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            index: HashMap::with_capacity(capacity),
+            slots: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn clear(&mut self) {
+        self.index.clear();
+        self.slots.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// Returns the slot holding `document`, if any.
+    fn find(&self, hash: u64, document: &WvgDocument) -> Option<usize> {
+        self.index
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|&slot| self.slots[slot].document == *document)
+    }
+
+    /// Unlinks `slot` from the recency list.
+    fn detach(&mut self, slot: usize) {
+        let (prev, next) = (self.slots[slot].prev, self.slots[slot].next);
+        match prev {
+            NIL => self.head = next,
+            prev => self.slots[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.slots[next].prev = prev,
+        }
+    }
+
+    /// Links `slot` in as the most recently used entry.
+    fn push_back(&mut self, slot: usize) {
+        self.slots[slot].prev = self.tail;
+        self.slots[slot].next = NIL;
+        match self.tail {
+            NIL => self.head = slot,
+            tail => self.slots[tail].next = slot,
+        }
+        self.tail = slot;
+    }
+
+    /// Marks `slot` as the most recently used entry.
+    fn touch(&mut self, slot: usize) {
+        if self.tail != slot {
+            self.detach(slot);
+            self.push_back(slot);
+        }
+    }
+
+    /// Inserts an output, reusing the least recently used slot once the
+    /// cache holds `capacity` entries.
+    fn insert(&mut self, hash: u64, document: &WvgDocument, output: T, capacity: usize) {
+        if let Some(slot) = self.find(hash, document) {
+            self.slots[slot].output = output;
+            self.touch(slot);
+            return;
+        }
+
+        let entry = Entry {
+            hash,
+            document: document.clone(),
+            output,
+            prev: NIL,
+            next: NIL,
+        };
+        let slot = if self.slots.len() < capacity {
+            self.slots.push(entry);
+            self.slots.len() - 1
+        } else {
+            let slot = self.head;
+            self.detach(slot);
+            let evicted = self.slots[slot].hash;
+            if let Some(bucket) = self.index.get_mut(&evicted) {
+                bucket.retain(|&s| s != slot);
+                if bucket.is_empty() {
+                    self.index.remove(&evicted);
+                }
+            }
+            self.slots[slot] = entry;
+            slot
+        };
+        self.index.entry(hash).or_default().push(slot);
+        self.push_back(slot);
+    }
+}
+
+impl<C: Converter> CachedConverter<C> {
+    /// Creates a cache of at most `capacity` outputs around `inner`.
+    ///
+    /// A capacity of 0 disables caching.
+    pub fn new(inner: C, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            cache: Mutex::new(LruCache::with_capacity(capacity)),
+        }
+    }
+
+    /// Returns the wrapped converter.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the number of cached outputs.
+    pub fn len(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Returns whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached outputs.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+impl<C> Converter for CachedConverter<C>
+where
+    C: Converter,
+    C::Output: Clone,
+{
+    type Output = C::Output;
+
+    fn convert(&self, document: &WvgDocument) -> WvgResult<Self::Output> {
+        if self.capacity == 0 {
+            return self.inner.convert(document);
+        }
+
+        let mut hasher = DefaultHasher::new();
+        document.hash(&mut hasher);
+        let key = hasher.finish();
+
+        {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(slot) = cache.find(key, document) {
+                trace!("Cache hit for document {:016x}", key);
+                cache.touch(slot);
+                return Ok(cache.slots[slot].output.clone());
+            }
+        }
+
+        trace!("Cache miss for document {:016x}", key);
+        let output = self.inner.convert(document)?;
+
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, document, output.clone(), self.capacity);
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use std::cell::Cell;
+
+    /// Converter returning the element count and counting its invocations.
+    struct CountingConverter {
+        calls: Cell<usize>,
+    }
+
+    impl Converter for CountingConverter {
+        type Output = usize;
+
+        fn convert(&self, document: &WvgDocument) -> WvgResult<Self::Output> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(document.elements.len())
+        }
+    }
+
+    fn document(num_elements: usize) -> WvgDocument {
+        WvgDocument {
+            header: WvgHeader {
+                general_info: GeneralInfo::default(),
+                color_config: ColorConfig::default(),
+                codec_params: CodecParams {
+                    element_masks: vec![false; 8],
+                    attribute_masks: AttributeMasks::default(),
+                    generic_params: GenericParams::default(),
+                    coord_params: CoordinateParams::Compact(CompactCoordinateParams::default()),
+                },
+                animation_mode: None,
+            },
+            elements: (0..num_elements)
+                .map(|i| WvgElement {
+                    id: format!("el_{}", i),
+                    span: None,
+                    data: ElementData::GroupEnd,
+                })
+                .collect(),
+        }
+    }
+
+    fn cached(capacity: usize) -> CachedConverter<CountingConverter> {
+        let inner = CountingConverter {
+            calls: Cell::new(0),
+        };
+        CachedConverter::new(inner, capacity)
+    }
+
+    #[test]
+    fn test_identical_documents_hit_cache() {
+        let converter = cached(4);
+        assert_eq!(converter.convert(&document(2)).unwrap(), 2);
+        assert_eq!(converter.convert(&document(2)).unwrap(), 2);
+        assert_eq!(converter.inner().calls.get(), 1);
+        assert_eq!(converter.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let converter = cached(2);
+        converter.convert(&document(1)).unwrap();
+        converter.convert(&document(2)).unwrap();
+        // Touch the first document so the second becomes least recently used
+        converter.convert(&document(1)).unwrap();
+        converter.convert(&document(3)).unwrap();
+        assert_eq!(converter.inner().calls.get(), 3);
+
+        converter.convert(&document(1)).unwrap();
+        assert_eq!(converter.inner().calls.get(), 3);
+        converter.convert(&document(2)).unwrap();
+        assert_eq!(converter.inner().calls.get(), 4);
+        assert_eq!(converter.len(), 2);
+    }
+
+    #[test]
+    fn test_hash_collision_compares_documents() {
+        let mut cache = LruCache::with_capacity(2);
+        cache.insert(7, &document(1), 1, 2);
+        cache.insert(7, &document(2), 2, 2);
+        assert_eq!(cache.len(), 2);
+
+        let first = cache.find(7, &document(1)).unwrap();
+        let second = cache.find(7, &document(2)).unwrap();
+        assert_eq!(cache.slots[first].output, 1);
+        assert_eq!(cache.slots[second].output, 2);
+        assert!(cache.find(7, &document(3)).is_none());
+
+        // Evicting one colliding entry keeps the other reachable
+        cache.touch(first);
+        cache.insert(9, &document(3), 3, 2);
+        assert!(cache.find(7, &document(2)).is_none());
+        assert_eq!(cache.slots[cache.find(7, &document(1)).unwrap()].output, 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let converter = cached(0);
+        converter.convert(&document(1)).unwrap();
+        converter.convert(&document(1)).unwrap();
+        assert_eq!(converter.inner().calls.get(), 2);
+        assert!(converter.is_empty());
+    }
+}
@@ -5,7 +5,7 @@
 //! or any other format.
 
 use crate::error::WvgResult;
-use crate::types::WvgDocument;
+use crate::types::{Color, WvgDocument};
 
 /// A trait for converting WVG documents to other formats.
 ///
@@ -53,7 +53,7 @@ pub trait Converter {
 ///
 /// This struct provides common configuration that may be used by various
 /// converter implementations.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct ConverterConfig {
     /// Whether to include comments in the output (if supported).
     pub include_comments: bool,
@@ -63,6 +63,193 @@ pub struct ConverterConfig {
 
     /// Custom line width multiplier.
     pub line_width_scale: Option<f32>,
+
+    /// Factor applied to the canvas size, all emitted coordinates and the
+    /// stroke widths.
+    ///
+    /// Values other than `1.0` cause coordinates to be emitted as floats.
+    pub output_scale: f64,
+
+    /// Grid that emitted coordinates are rounded to, e.g. `1.0` for whole
+    /// units or `0.5` for half units, after applying the output scale.
+    ///
+    /// Applies to path, circle and transform coordinates; relative offsets
+    /// are taken between rounded positions so they do not drift. Arc radii
+    /// and the canvas size are left unrounded.
+    pub snap_grid: Option<f64>,
+
+    /// Background color overriding the one declared in the document.
+    pub background_color: Option<Color>,
+
+    /// Whether to emit reuse arrays as a single row that is itself reused,
+    /// rather than one `<use>` per array cell.
+    pub array_as_nested_use: bool,
+
+    /// Rule deciding which parts of a self-intersecting filled shape are inside.
+    pub fill_rule: FillRule,
+
+    /// Whether to annotate emitted nodes with `data-wvg-type` and
+    /// `data-wvg-index` attributes naming their source element, and the
+    /// root with a `data-wvg-version` attribute.
+    pub emit_data_attributes: bool,
+
+    /// Prefix prepended to every emitted id and id reference, so several
+    /// converted documents can share one page.
+    pub id_prefix: String,
+
+    /// Whether to leave out the ids of the `<use>` instances emitted for
+    /// array reuse elements, which are rarely addressed individually.
+    pub omit_array_ids: bool,
+
+    /// Whether to reference shared CSS classes instead of inlining styles.
+    pub use_css_classes: bool,
+
+    /// Whether to produce the smallest possible output, e.g. for data URIs.
+    ///
+    /// Drops the XML declaration, compacts the style block, shortens colors
+    /// and removes unnecessary whitespace from path data.
+    pub minify: bool,
+
+    /// Whether to start the output with an XML declaration.
+    ///
+    /// Disable this for SVG inlined into HTML5, where the declaration is
+    /// invalid. Minified output never includes it.
+    pub emit_xml_declaration: bool,
+
+    /// Whether to emit polyline segments as absolute `L x y` commands rather
+    /// than relative `l dx dy` commands.
+    pub absolute_paths: bool,
+
+    /// Whether to emit circular polyline segments as relative `a` and `l`
+    /// commands, which are usually shorter. Ignored when `absolute_paths`
+    /// is set.
+    pub relative_arcs: bool,
+
+    /// Column at which path data is wrapped onto a new line, breaking only
+    /// between commands. Ignored when minifying.
+    pub wrap_path_data: Option<usize>,
+
+    /// Whether to declare the `xlink` namespace and reference `<use>` targets
+    /// with `xlink:href`, for legacy SVG 1.1 renderers.
+    pub xlink_compat: bool,
+
+    /// Margin added to the viewBox on all sides, in output units, so strokes
+    /// along the drawing edges are not clipped.
+    pub padding: f64,
+
+    /// Upper bound for the rendered width and height, e.g. for thumbnails.
+    ///
+    /// Larger drawings are scaled down, preserving their aspect ratio.
+    pub max_dimension: Option<u32>,
+
+    /// Largest number of instances a single array reuse element may expand
+    /// to; larger arrays fail with `WvgError::LimitExceeded` instead of
+    /// producing an enormous SVG.
+    ///
+    /// `PathDataConverter` and `SceneConverter` copy reused geometry, so for
+    /// them this bounds the total number of reuse instances expanded,
+    /// including nested ones.
+    ///
+    /// Grids are held to the same bound on the number of lines they draw.
+    pub max_array_instances: usize,
+
+    /// Whether to clamp emitted polyline coordinates into the declared
+    /// drawing bounds. This is lossy: out-of-bounds geometry is flattened
+    /// onto the canvas edge.
+    pub clamp_to_bounds: bool,
+
+    /// How groups with an unset display bit are hidden.
+    pub hidden_mode: HiddenMode,
+
+    /// Transform for a group wrapping all converted elements, e.g. to place
+    /// the drawing inside a larger scene.
+    pub root_transform: Option<RootTransform>,
+
+    /// Encoding used by `SvgConverter::convert_data_uri`.
+    pub data_uri_encoding: DataUriEncoding,
+
+    /// Whether to emit ARIA roles and labels for assistive technology.
+    ///
+    /// The root gets `role="img"` labelled with the document title, and
+    /// groups get `role="group"` labelled with their position.
+    pub emit_aria: bool,
+}
+
+/// Rule for determining the interior of self-intersecting filled shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// A point is inside if the winding number is non-zero.
+    #[default]
+    NonZero,
+    /// A point is inside if a ray from it crosses the outline an odd number of times.
+    EvenOdd,
+}
+
+/// Translation and uniform scale applied to the whole drawing.
+///
+/// Emitted as `translate(x, y) scale(s)`, so the translation is in output
+/// units and not affected by the scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootTransform {
+    /// Horizontal translation.
+    pub translate_x: f64,
+    /// Vertical translation.
+    pub translate_y: f64,
+    /// Uniform scale factor.
+    pub scale: f64,
+}
+
+/// Encoding of the SVG inside a `data:` URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataUriEncoding {
+    /// Percent-encoding, which is smaller for text such as SVG.
+    #[default]
+    Percent,
+    /// Base64, which is safer where the URI is embedded unquoted.
+    Base64,
+}
+
+/// Attribute used to hide groups whose display bit is unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HiddenMode {
+    /// `display="none"`, which removes the group from layout entirely.
+    #[default]
+    Display,
+    /// `visibility="hidden"`, which keeps the group's layout and bounding box.
+    Visibility,
+}
+
+impl Default for ConverterConfig {
+    fn default() -> Self {
+        Self {
+            include_comments: false,
+            pretty_print: false,
+            line_width_scale: None,
+            output_scale: 1.0,
+            snap_grid: None,
+            background_color: None,
+            array_as_nested_use: false,
+            fill_rule: FillRule::default(),
+            emit_data_attributes: false,
+            id_prefix: String::new(),
+            omit_array_ids: false,
+            use_css_classes: false,
+            minify: false,
+            emit_xml_declaration: true,
+            absolute_paths: false,
+            relative_arcs: false,
+            wrap_path_data: None,
+            xlink_compat: false,
+            padding: 0.0,
+            max_dimension: None,
+            max_array_instances: 4096,
+            clamp_to_bounds: false,
+            hidden_mode: HiddenMode::default(),
+            root_transform: None,
+            data_uri_encoding: DataUriEncoding::default(),
+            emit_aria: false,
+        }
+    }
 }
 
 impl ConverterConfig {
@@ -71,6 +258,31 @@ impl ConverterConfig {
         Self::default()
     }
 
+    /// Creates a configuration for SVG served on the web: minified, without
+    /// an XML declaration and with shared CSS classes instead of inline
+    /// styles.
+    pub fn preset_web() -> Self {
+        Self::new()
+            .with_minify(true)
+            .with_xml_declaration(false)
+            .with_css_classes(true)
+    }
+
+    /// Creates a configuration for print and further editing: pretty-printed,
+    /// with absolute path commands.
+    ///
+    /// Coordinates are always emitted at full precision unless `snap_grid`
+    /// is set, so there is no precision setting to raise.
+    pub fn preset_print() -> Self {
+        Self::new().with_pretty_print(true).with_absolute_paths(true)
+    }
+
+    /// Creates a configuration for inspecting conversions: comments and
+    /// `data-wvg-*` attributes tying the output to the source elements.
+    pub fn preset_debug() -> Self {
+        Self::new().with_comments(true).with_data_attributes(true)
+    }
+
     /// Sets whether to include comments in the output.
     pub fn with_comments(mut self, include: bool) -> Self {
         self.include_comments = include;
@@ -88,4 +300,207 @@ impl ConverterConfig {
         self.line_width_scale = Some(scale);
         self
     }
+
+    /// Sets the output scale factor.
+    pub fn with_output_scale(mut self, scale: f64) -> Self {
+        self.output_scale = scale;
+        self
+    }
+
+    /// Sets the grid emitted coordinates are rounded to.
+    pub fn with_snap_grid(mut self, grid: f64) -> Self {
+        self.snap_grid = Some(grid);
+        self
+    }
+
+    /// Sets the background color override.
+    pub fn with_background_color(mut self, color: Color) -> Self {
+        self.background_color = Some(color);
+        self
+    }
+
+    /// Sets whether to emit reuse arrays as nested `<use>` elements.
+    pub fn with_array_as_nested_use(mut self, nested: bool) -> Self {
+        self.array_as_nested_use = nested;
+        self
+    }
+
+    /// Sets the fill rule for filled shapes.
+    pub fn with_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    /// Sets whether to emit `data-wvg-*` attributes.
+    pub fn with_data_attributes(mut self, emit: bool) -> Self {
+        self.emit_data_attributes = emit;
+        self
+    }
+
+    /// Sets the prefix for emitted ids.
+    pub fn with_id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.id_prefix = prefix.into();
+        self
+    }
+
+    /// Sets whether to omit the ids of array reuse instances.
+    pub fn with_omit_array_ids(mut self, omit: bool) -> Self {
+        self.omit_array_ids = omit;
+        self
+    }
+
+    /// Sets whether to use shared CSS classes for element styles.
+    pub fn with_css_classes(mut self, use_classes: bool) -> Self {
+        self.use_css_classes = use_classes;
+        self
+    }
+
+    /// Sets whether to minify the output.
+    pub fn with_minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    /// Sets whether to emit the XML declaration.
+    pub fn with_xml_declaration(mut self, emit: bool) -> Self {
+        self.emit_xml_declaration = emit;
+        self
+    }
+
+    /// Sets whether to emit absolute path commands for polylines.
+    pub fn with_absolute_paths(mut self, absolute: bool) -> Self {
+        self.absolute_paths = absolute;
+        self
+    }
+
+    /// Sets whether to emit relative arc commands.
+    pub fn with_relative_arcs(mut self, relative: bool) -> Self {
+        self.relative_arcs = relative;
+        self
+    }
+
+    /// Wraps path data at the given column.
+    pub fn with_wrap_path_data(mut self, column: usize) -> Self {
+        self.wrap_path_data = Some(column);
+        self
+    }
+
+    /// Sets whether to use `xlink:href` for `<use>` references.
+    pub fn with_xlink_compat(mut self, compat: bool) -> Self {
+        self.xlink_compat = compat;
+        self
+    }
+
+    /// Sets the viewBox padding.
+    pub fn with_padding(mut self, padding: f64) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the maximum rendered width and height.
+    pub fn with_max_dimension(mut self, max: u32) -> Self {
+        self.max_dimension = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of instances per array reuse element.
+    pub fn with_max_array_instances(mut self, max: usize) -> Self {
+        self.max_array_instances = max;
+        self
+    }
+
+    /// Sets whether to clamp coordinates into the drawing bounds.
+    pub fn with_clamp_to_bounds(mut self, clamp: bool) -> Self {
+        self.clamp_to_bounds = clamp;
+        self
+    }
+
+    /// Sets how hidden groups are emitted.
+    pub fn with_hidden_mode(mut self, hidden_mode: HiddenMode) -> Self {
+        self.hidden_mode = hidden_mode;
+        self
+    }
+
+    /// Wraps all elements in a group translated by `(x, y)` and scaled by `scale`.
+    pub fn with_root_transform(mut self, x: f64, y: f64, scale: f64) -> Self {
+        self.root_transform = Some(RootTransform {
+            translate_x: x,
+            translate_y: y,
+            scale,
+        });
+        self
+    }
+
+    /// Sets the encoding used for `data:` URIs.
+    pub fn with_data_uri_encoding(mut self, encoding: DataUriEncoding) -> Self {
+        self.data_uri_encoding = encoding;
+        self
+    }
+
+    /// Sets whether to emit ARIA roles and labels.
+    pub fn with_aria(mut self, emit: bool) -> Self {
+        self.emit_aria = emit;
+        self
+    }
+
+    /// Returns the rendered size of a drawing of the given size, scaled down
+    /// to fit within `max_dimension` while preserving the aspect ratio.
+    pub fn fit_size(&self, width: f64, height: f64) -> (f64, f64) {
+        let Some(max) = self.max_dimension else {
+            return (width, height);
+        };
+
+        let factor = (f64::from(max) / width.max(height)).min(1.0);
+        (width * factor, height * factor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_web() {
+        let config = ConverterConfig::preset_web();
+        assert!(config.minify);
+        assert!(!config.emit_xml_declaration);
+        assert!(config.use_css_classes);
+        assert!(!config.pretty_print);
+    }
+
+    #[test]
+    fn test_preset_print() {
+        let config = ConverterConfig::preset_print();
+        assert!(config.pretty_print);
+        assert!(config.absolute_paths);
+        assert!(!config.minify);
+        assert_eq!(config.snap_grid, None);
+    }
+
+    #[test]
+    fn test_preset_debug() {
+        let config = ConverterConfig::preset_debug();
+        assert!(config.include_comments);
+        assert!(config.emit_data_attributes);
+        assert!(!config.minify);
+    }
+
+    #[test]
+    fn test_fit_size_landscape() {
+        let config = ConverterConfig::new().with_max_dimension(64);
+        assert_eq!(config.fit_size(128.0, 32.0), (64.0, 16.0));
+    }
+
+    #[test]
+    fn test_fit_size_portrait() {
+        let config = ConverterConfig::new().with_max_dimension(50);
+        assert_eq!(config.fit_size(40.0, 200.0), (10.0, 50.0));
+    }
+
+    #[test]
+    fn test_fit_size_small_drawing_unchanged() {
+        let config = ConverterConfig::new().with_max_dimension(64);
+        assert_eq!(config.fit_size(32.0, 16.0), (32.0, 16.0));
+        assert_eq!(ConverterConfig::new().fit_size(128.0, 32.0), (128.0, 32.0));
+    }
 }
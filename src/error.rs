@@ -6,6 +6,8 @@
 use std::fmt;
 use thiserror::Error;
 
+use crate::types::CharacterSizeHeader;
+
 pub type WvgResult<T> = Result<T, WvgError>;
 
 /// Errors that can occur during WVG parsing and conversion.
@@ -15,6 +17,11 @@ pub enum WvgError {
     #[error("unexpected end of stream")]
     EndOfStream,
 
+    /// The data is a Character Size WVG, whose elements are not supported.
+    /// Carries the decoded header.
+    #[error("Character Size WVG (version {}) is not supported", .0.general_info.version)]
+    CharacterSizeWvg(Box<CharacterSizeHeader>),
+
     /// The WVG type indicator is invalid.
     #[error("invalid WVG type: expected 0 (character size) or 1 (standard)")]
     InvalidWvgType,
@@ -43,6 +50,59 @@ pub enum WvgError {
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// The header declares bit widths that cannot describe valid data.
+    #[error("invalid coordinate parameters: {0}")]
+    InvalidCoordinateParams(String),
+
+    /// A color index does not refer to an entry of the active palette.
+    #[error("color index {index} out of bounds for palette of {palette_len} colors")]
+    InvalidColorIndex {
+        /// The invalid color index.
+        index: u32,
+        /// The number of colors in the palette.
+        palette_len: usize,
+    },
+
+    /// A coordinate computation exceeded the representable range.
+    #[error("coordinate overflow")]
+    CoordinateOverflow,
+
+    /// The angle resolution code is outside the valid range 0-3.
+    #[error("invalid angle resolution: {0}")]
+    InvalidAngleResolution(u8),
+
+    /// A declared size is larger than the data could possibly hold.
+    #[error("{what} of {value} exceeds the limit of {limit}")]
+    LimitExceeded {
+        /// What was being counted.
+        what: &'static str,
+        /// The declared value.
+        value: usize,
+        /// The largest value the data allows.
+        limit: usize,
+    },
+
+    /// An error raised while parsing a specific element, with the element
+    /// index and the raw (unmapped) element type index if it was read.
+    #[error("error in element {index}: {source}")]
+    ElementError {
+        /// Index of the element being parsed.
+        index: usize,
+        /// Raw element type index read from the stream.
+        raw_type: Option<u32>,
+        /// The underlying error.
+        source: Box<WvgError>,
+    },
+
+    /// An error raised while parsing a part of the header, naming that part.
+    #[error("error while parsing {stage}: {source}")]
+    StageError {
+        /// The part of the data being parsed.
+        stage: ParseStage,
+        /// The underlying error.
+        source: Box<WvgError>,
+    },
+
     /// Element index out of bounds in reuse element.
     #[error("element index {index} out of bounds (max: {max})")]
     ElementIndexOutOfBounds {
@@ -51,6 +111,16 @@ pub enum WvgError {
         /// The maximum valid index.
         max: usize,
     },
+
+    /// A reuse element references itself or an element after it, which an
+    /// SVG `<use>` cannot reliably point to.
+    #[error("reuse element {position} references element {index}, which does not precede it")]
+    InvalidReuse {
+        /// The referenced element index.
+        index: u32,
+        /// Index of the reuse element itself.
+        position: usize,
+    },
 }
 
 /// Features that are not yet implemented in the parser.
@@ -64,7 +134,7 @@ pub enum UnsupportedFeature {
     BezierPolyline,
     /// Polygon elements.
     Polygon,
-    /// Special shape elements (regular polygon, star, grid).
+    /// Special shape elements other than stars (regular polygon, grid).
     SpecialShape,
     /// Text elements.
     TextElement,
@@ -89,7 +159,7 @@ impl fmt::Display for UnsupportedFeature {
             UnsupportedFeature::CompactCoordinateMode => "Compact coordinate mode",
             UnsupportedFeature::BezierPolyline => "Bezier polyline elements",
             UnsupportedFeature::Polygon => "Polygon elements",
-            UnsupportedFeature::SpecialShape => "Special shape elements (regular polygon, star, grid)",
+            UnsupportedFeature::SpecialShape => "Special shape elements other than stars (regular polygon, grid)",
             UnsupportedFeature::TextElement => "Text elements",
             UnsupportedFeature::SimpleAnimation => "Simple animation elements",
             UnsupportedFeature::StandardAnimation => "Standard animation elements",
@@ -101,3 +171,67 @@ impl fmt::Display for UnsupportedFeature {
         write!(f, "{}", description)
     }
 }
+
+/// Parts of a WVG file reported by `WvgError::StageError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseStage {
+    /// Version, extended information, author, title and timestamp.
+    GeneralInfo,
+    /// Color scheme, default colors and palette.
+    ColorConfiguration,
+    /// Element type masks.
+    ElementMasks,
+    /// Attribute masks.
+    AttributeMasks,
+    /// Generic codec parameters.
+    GenericParameters,
+    /// Coordinate mode, drawing size and coordinate bit widths.
+    CoordinateParameters,
+    /// Animation settings.
+    AnimationSettings,
+    /// Number of elements.
+    ElementCount,
+}
+
+impl fmt::Display for ParseStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            ParseStage::GeneralInfo => "general information",
+            ParseStage::ColorConfiguration => "color configuration",
+            ParseStage::ElementMasks => "element masks",
+            ParseStage::AttributeMasks => "attribute masks",
+            ParseStage::GenericParameters => "generic parameters",
+            ParseStage::CoordinateParameters => "coordinate parameters",
+            ParseStage::AnimationSettings => "animation settings",
+            ParseStage::ElementCount => "element count",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+/// Non-fatal problems found in the input that were worked around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// An arc's radius was too large to render reliably, so it was drawn as
+    /// a straight line.
+    DegenerateArc {
+        /// Index of the element containing the arc.
+        element_index: usize,
+    },
+    /// The declared drawing width or height was zero, so the extent of the
+    /// content was used for that dimension instead.
+    ZeroDimension,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::DegenerateArc { element_index } => {
+                write!(f, "degenerate arc in element {} drawn as a line", element_index)
+            }
+            ParseWarning::ZeroDimension => {
+                write!(f, "zero drawing dimension replaced by the content extent")
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! HTML converter implementation for WVG documents.
+//!
+//! This module provides a `Converter` that wraps the SVG output in a minimal,
+//! self-contained HTML document suitable for viewing in a web browser.
+
+use std::fmt::Write;
+
+use crate::converter::{Converter, ConverterConfig};
+use crate::error::WvgResult;
+use crate::svg::{color_to_hex, SvgConverter};
+use crate::types::WvgDocument;
+use crate::log::debug;
+
+/// Converter that produces a standalone HTML page embedding the SVG output.
+///
+/// The SVG is generated by an internal `SvgConverter` using the same
+/// configuration, so all SVG options apply to the embedded image as well.
+///
+/// # Example
+///
+/// ```ignore
+/// use wvg::{BitStream, WvgParser, HtmlConverter, Converter};
+///
+/// let data = std::fs::read("input.wvg")?;
+/// let mut bs = BitStream::new(&data);
+/// let document = WvgParser::new(&mut bs).parse()?;
+///
+/// let html = HtmlConverter::new().convert(&document)?;
+/// std::fs::write("output.html", html)?;
+/// ```
+pub struct HtmlConverter {
+    /// Configuration options.
+    config: ConverterConfig,
+}
+
+impl HtmlConverter {
+    /// Creates a new HTML converter with default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: ConverterConfig::default(),
+        }
+    }
+
+    /// Creates a new HTML converter with the given configuration.
+    pub fn with_config(config: ConverterConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Default for HtmlConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for HtmlConverter {
+    type Output = String;
+
+    fn convert(&self, document: &WvgDocument) -> WvgResult<Self::Output> {
+        let svg = SvgConverter::with_config(self.config.clone()).convert(document)?;
+        debug!("Wrapping {} bytes of SVG in HTML", svg.len());
+
+        // The XML declaration is not allowed inside an HTML body
+        let svg = match svg.strip_prefix("<?xml") {
+            Some(rest) => rest.split_once("?>").map_or(rest, |(_, body)| body).trim_start(),
+            None => svg.as_str(),
+        };
+
+        let title = document
+            .header
+            .general_info
+            .title
+            .as_deref()
+            .filter(|t| !t.is_empty())
+            .unwrap_or("WVG");
+
+        let background = self
+            .config
+            .background_color
+            .as_ref()
+            .or(document.header.color_config.background_color.as_ref())
+            .map(color_to_hex)
+            .unwrap_or_else(|| "transparent".to_string());
+
+        let mut html = String::with_capacity(svg.len() + 512);
+        html.push_str("<!DOCTYPE html>\n");
+        html.push_str("<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        writeln!(&mut html, "<title>{}</title>", escape_html(title)).unwrap();
+        html.push_str("<style>\n");
+        html.push_str("html, body { margin: 0; height: 100%; }\n");
+        writeln!(
+            &mut html,
+            "body {{ display: flex; align-items: center; justify-content: center; background: {}; }}",
+            background
+        )
+        .unwrap();
+        html.push_str(".wvg-container { width: 100%; height: 100%; }\n");
+        html.push_str(".wvg-container svg { width: 100%; height: 100%; }\n");
+        html.push_str("</style>\n</head>\n<body>\n");
+        html.push_str("<div class=\"wvg-container\">\n");
+        html.push_str(svg);
+        if !svg.ends_with('\n') {
+            html.push('\n');
+        }
+        html.push_str("</div>\n</body>\n</html>\n");
+
+        Ok(html)
+    }
+}
+
+/// Escapes text for inclusion in HTML content or quoted XML attributes.
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
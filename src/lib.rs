@@ -16,16 +16,27 @@
 //! ```
 
 pub mod bitstream;
+pub mod cache;
 pub mod converter;
 pub mod error;
+pub mod html;
+mod log;
+pub mod normalize;
 pub mod parser;
+pub mod path;
+pub mod scene;
 pub mod svg;
 pub mod types;
 
 // Re-export main types for convenient access
-pub use bitstream::BitStream;
+pub use bitstream::{BitPos, BitStream};
+pub use cache::CachedConverter;
 pub use converter::Converter;
-pub use error::{WvgError, WvgResult};
-pub use parser::WvgParser;
+pub use error::{ParseStage, ParseWarning, WvgError, WvgResult};
+pub use html::HtmlConverter;
+pub use normalize::normalize;
+pub use parser::{ParseOptions, WvgParser};
+pub use path::{PathDataConverter, Primitive, Stroke};
+pub use scene::{PathCommand, Scene, SceneConverter, SceneGroup, SceneNode, ScenePath};
 pub use svg::SvgConverter;
 pub use types::*;
@@ -0,0 +1,66 @@
+//! Logging macros.
+//!
+//! The library logs through these macros instead of using `tracing`
+//! directly. With the `tracing` feature (enabled by default) they forward to
+//! the `tracing` macros of the same name; without it they expand to dead code
+//! that only type-checks the arguments, so nothing is evaluated or logged.
+
+#[cfg(feature = "tracing")]
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {
+        ::tracing::$level!($($arg)+)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! log_event {
+    ($level:ident, $($arg:tt)+) => {
+        if false {
+            $crate::log::discard!($($arg)+);
+        }
+    };
+}
+
+/// Type-checks the fields and message of a log event without using them.
+#[cfg(not(feature = "tracing"))]
+macro_rules! discard {
+    ($name:ident = ?$value:expr, $($rest:tt)+) => {{
+        let _ = &$value;
+        $crate::log::discard!($($rest)+);
+    }};
+    ($name:ident = %$value:expr, $($rest:tt)+) => {{
+        let _ = &$value;
+        $crate::log::discard!($($rest)+);
+    }};
+    ($name:ident = $value:expr, $($rest:tt)+) => {{
+        let _ = &$value;
+        $crate::log::discard!($($rest)+);
+    }};
+    ($name:ident, $($rest:tt)+) => {{
+        let _ = &$name;
+        $crate::log::discard!($($rest)+);
+    }};
+    ($($arg:tt)+) => {
+        let _ = ::core::format_args!($($arg)+);
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::log::log_event!(trace, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log::log_event!(debug, $($arg)+) };
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log::log_event!(info, $($arg)+) };
+}
+
+macro_rules! warn_event {
+    ($($arg:tt)+) => { $crate::log::log_event!(warn, $($arg)+) };
+}
+
+pub(crate) use {debug, info, log_event, trace, warn_event as warn};
+#[cfg(not(feature = "tracing"))]
+pub(crate) use discard;
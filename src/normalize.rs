@@ -0,0 +1,211 @@
+//! Canonicalization of WVG documents.
+//!
+//! This module provides `normalize`, which recomputes the codec parameters of
+//! a document from the data it actually contains, so semantically equal
+//! documents end up with identical parameters.
+
+use crate::types::*;
+use crate::log::debug;
+
+/// Returns a copy of `document` whose flat coordinate parameters use the
+/// smallest bit widths that fit its data.
+///
+/// Coordinate widths cover every resolved point, offset widths cover every
+/// relative step (both offset levels get the same width, so the per-element
+/// level choice no longer matters), and the translation and point count
+/// widths cover the transforms and polylines present. Signed widths assume
+/// two's complement. Elements, drawing size and all other header fields are
+/// unchanged, as are documents using compact coordinates.
+///
+/// Widths are not capped at the 4-bit header fields; documents parsed from
+/// WVG data always fit, hand-built ones may not.
+pub fn normalize(document: &WvgDocument) -> WvgDocument {
+    let mut normalized = document.clone();
+    if let CoordinateParams::Flat(params) = &mut normalized.header.codec_params.coord_params {
+        let ranges = Ranges::collect(&document.elements);
+        ranges.apply(params);
+        debug!("Normalized flat coordinate parameters: {:?}", params);
+    }
+    normalized
+}
+
+/// Value ranges found in the elements of a document.
+#[derive(Default)]
+struct Ranges {
+    min: (i32, i32),
+    max: (i32, i32),
+    /// Largest bit width needed by an X or Y offset.
+    offset_bits: (u8, u8),
+    /// Largest bit width needed by a translation or center value.
+    translate_bits: u8,
+    /// Largest number of points following the leading point(s).
+    num_points: u32,
+}
+
+impl Ranges {
+    fn collect(elements: &[WvgElement]) -> Self {
+        let mut ranges = Self::default();
+
+        for element in elements {
+            match &element.data {
+                ElementData::Polyline(pl) => {
+                    for (i, p) in pl.points.iter().enumerate() {
+                        ranges.include_point(p.x, p.y);
+                        if i > 0 {
+                            // Steps too large for i32 saturate; they need
+                            // the full 32 bits either way
+                            let prev = pl.points[i - 1];
+                            ranges.include_offset(
+                                p.x.saturating_sub(prev.x),
+                                p.y.saturating_sub(prev.y),
+                            );
+                        }
+                    }
+                    ranges.include_count(pl.points.len().saturating_sub(1));
+                }
+                ElementData::CircularPolyline(cp) => {
+                    let mut current = (0i32, 0i32);
+                    for pt in &cp.points {
+                        current = if pt.is_absolute {
+                            (pt.point.x, pt.point.y)
+                        } else {
+                            ranges.include_offset(pt.point.x, pt.point.y);
+                            (
+                                current.0.saturating_add(pt.point.x),
+                                current.1.saturating_add(pt.point.y),
+                            )
+                        };
+                        ranges.include_point(current.0, current.1);
+                    }
+                    ranges.include_count(cp.points.len().saturating_sub(2));
+                }
+                ElementData::Reuse(reuse) => ranges.include_transform(&reuse.transform),
+                ElementData::GroupStart(gs) => {
+                    if let Some(transform) = &gs.transform {
+                        ranges.include_transform(transform);
+                    }
+                }
+                ElementData::LocalEnvelope(envelope) => {
+                    let Point { x, y } = envelope.origin;
+                    ranges.include_point(x, y);
+                    ranges.include_point(
+                        x.saturating_add(envelope.width),
+                        y.saturating_add(envelope.height),
+                    );
+                }
+                ElementData::SimpleShape(_)
+                | ElementData::SpecialShape(_)
+                | ElementData::GroupEnd => {}
+            }
+        }
+
+        ranges
+    }
+
+    fn include_point(&mut self, x: i32, y: i32) {
+        self.min = (self.min.0.min(x), self.min.1.min(y));
+        self.max = (self.max.0.max(x), self.max.1.max(y));
+    }
+
+    fn include_offset(&mut self, dx: i32, dy: i32) {
+        self.offset_bits.0 = self.offset_bits.0.max(signed_bits(dx));
+        self.offset_bits.1 = self.offset_bits.1.max(signed_bits(dy));
+    }
+
+    fn include_count(&mut self, count: usize) {
+        self.num_points = self.num_points.max(u32::try_from(count).unwrap_or(u32::MAX));
+    }
+
+    fn include_transform(&mut self, t: &Transform) {
+        for value in [t.translate_x, t.translate_y, t.cx, t.cy].into_iter().flatten() {
+            self.translate_bits = self.translate_bits.max(signed_bits(value));
+        }
+    }
+
+    fn apply(&self, params: &mut FlatCoordinateParams) {
+        params.xy_all_positive = self.min.0 >= 0 && self.min.1 >= 0;
+        let coordinate_bits = |min: i32, max: i32| {
+            if params.xy_all_positive {
+                unsigned_bits(max.unsigned_abs())
+            } else {
+                signed_bits(min).max(signed_bits(max))
+            }
+        };
+        params.max_x_in_bits = coordinate_bits(self.min.0, self.max.0);
+        params.max_y_in_bits = coordinate_bits(self.min.1, self.max.1);
+
+        // Keep the parameters valid: at least one nonzero coordinate width,
+        // and translations at most one bit wider than the wider coordinate
+        let widest = params.max_x_in_bits.max(params.max_y_in_bits).max(1);
+        let widest = widest.max(self.translate_bits.saturating_sub(1));
+        if params.max_x_in_bits >= params.max_y_in_bits {
+            params.max_x_in_bits = widest;
+        } else {
+            params.max_y_in_bits = widest;
+        }
+
+        params.trans_xy_in_bits = self.translate_bits;
+        params.num_points_in_bits = unsigned_bits(self.num_points);
+        params.offset_x_in_bits_level1 = self.offset_bits.0;
+        params.offset_x_in_bits_level2 = self.offset_bits.0;
+        params.offset_y_in_bits_level1 = self.offset_bits.1;
+        params.offset_y_in_bits_level2 = self.offset_bits.1;
+    }
+}
+
+/// Returns the number of bits needed to store `value` unsigned.
+fn unsigned_bits(value: u32) -> u8 {
+    (u32::BITS - value.leading_zeros()) as u8
+}
+
+/// Returns the number of bits needed to store `value` in two's complement.
+///
+/// Zero needs no bits, since a zero-width read yields 0.
+fn signed_bits(value: i32) -> u8 {
+    match value {
+        0 => 0,
+        v if v > 0 => unsigned_bits(v as u32) + 1,
+        v => unsigned_bits(!v as u32) + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_bits() {
+        assert_eq!(signed_bits(0), 0);
+        assert_eq!(signed_bits(-1), 1);
+        assert_eq!(signed_bits(1), 2);
+        assert_eq!(signed_bits(-2), 2);
+        assert_eq!(signed_bits(127), 8);
+        assert_eq!(signed_bits(-128), 8);
+        assert_eq!(signed_bits(-129), 9);
+    }
+
+    #[test]
+    fn test_extreme_polyline_offsets() {
+        let points = [(i32::MIN, i32::MAX), (i32::MAX, i32::MIN), (i32::MIN, i32::MAX)];
+        let element = WvgElement {
+            id: "extreme".to_string(),
+            span: None,
+            data: ElementData::Polyline(PolylineElement {
+                attributes: ElementAttributes::default(),
+                points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            }),
+        };
+
+        let ranges = Ranges::collect(&[element]);
+        assert_eq!(ranges.offset_bits, (32, 32));
+        assert_eq!(ranges.min, (i32::MIN, i32::MIN));
+        assert_eq!(ranges.max, (i32::MAX, i32::MAX));
+    }
+
+    #[test]
+    fn test_unsigned_bits() {
+        assert_eq!(unsigned_bits(0), 0);
+        assert_eq!(unsigned_bits(1), 1);
+        assert_eq!(unsigned_bits(128), 8);
+    }
+}
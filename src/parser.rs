@@ -3,10 +3,103 @@
 //! This module provides the parser for WVG binary data, converting it into
 //! structured data types that can be further processed or converted to other formats.
 
-use crate::bitstream::BitStream;
-use crate::error::{UnsupportedFeature, WvgError, WvgResult};
+use crate::bitstream::{BitPos, BitStream};
+use crate::error::{ParseStage, UnsupportedFeature, WvgError, WvgResult};
 use crate::types::*;
-use tracing::{debug, info, trace, warn};
+use crate::log::{debug, info, trace, warn};
+
+/// Width of the character count preceding author and title strings.
+const STRING_LENGTH_BITS: u8 = 8;
+
+/// Maximum number of characters in an author or title string.
+pub const MAX_STRING_LENGTH: usize = (1 << STRING_LENGTH_BITS) - 1;
+
+/// Options controlling how the parser treats malformed input.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Whether to reject structurally invalid data instead of recovering from it.
+    pub strict: bool,
+    /// Whether to ignore the declared element count and read elements until
+    /// the data is exhausted.
+    pub read_until_eof: bool,
+    /// Whether to wrap errors raised inside an element in
+    /// `WvgError::ElementError`, naming the element and its raw type index.
+    pub element_context: bool,
+    /// Whether to wrap errors raised while parsing the header in
+    /// `WvgError::StageError`, naming the part of the header being read.
+    pub stage_context: bool,
+    /// Whether to decode UCS-2 strings strictly, one BMP code point per
+    /// 16-bit unit, instead of combining UTF-16 surrogate pairs.
+    pub strict_ucs2: bool,
+    /// Whether to record the byte range each element occupies in the input.
+    pub track_spans: bool,
+    /// Whether signed coordinates are encoded as sign-magnitude instead of
+    /// two's complement, as written by some legacy encoders.
+    pub sign_magnitude: bool,
+    /// Whether the second point of a circular polyline is encoded as an
+    /// offset from the first point instead of an absolute point.
+    ///
+    /// The reference output for the sample data requires an absolute second
+    /// point, but some encoders write it as an offset.
+    pub relative_second_point: bool,
+}
+
+impl ParseOptions {
+    /// Creates a new set of parse options with default (lenient) values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to reject structurally invalid data.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether to read elements until the end of the data.
+    pub fn with_read_until_eof(mut self, read_until_eof: bool) -> Self {
+        self.read_until_eof = read_until_eof;
+        self
+    }
+
+    /// Sets whether to report which element caused a parse error.
+    pub fn with_element_context(mut self, element_context: bool) -> Self {
+        self.element_context = element_context;
+        self
+    }
+
+    /// Sets whether to report which part of the header caused a parse error.
+    pub fn with_stage_context(mut self, stage_context: bool) -> Self {
+        self.stage_context = stage_context;
+        self
+    }
+
+    /// Sets whether to decode UCS-2 strings without combining surrogate pairs.
+    pub fn with_strict_ucs2(mut self, strict_ucs2: bool) -> Self {
+        self.strict_ucs2 = strict_ucs2;
+        self
+    }
+
+    /// Sets whether to record the byte range of each element.
+    ///
+    /// Spans are not part of element equality or hashing.
+    pub fn with_track_spans(mut self, track_spans: bool) -> Self {
+        self.track_spans = track_spans;
+        self
+    }
+
+    /// Sets whether signed coordinates use sign-magnitude encoding.
+    pub fn with_sign_magnitude(mut self, sign_magnitude: bool) -> Self {
+        self.sign_magnitude = sign_magnitude;
+        self
+    }
+
+    /// Sets whether the second circular polyline point is an offset.
+    pub fn with_relative_second_point(mut self, relative: bool) -> Self {
+        self.relative_second_point = relative;
+        self
+    }
+}
 
 /// Parser for WVG binary data.
 ///
@@ -15,6 +108,10 @@ use tracing::{debug, info, trace, warn};
 pub struct WvgParser<'a> {
     /// The bit stream to read from.
     bs: &'a mut BitStream<'a>,
+    /// Parse options.
+    options: ParseOptions,
+    /// Custom palette from the header, or the one supplied by the caller.
+    palette: Vec<Color>,
     /// Element masks from the header.
     element_masks: Vec<bool>,
     /// Attribute masks from the header.
@@ -33,12 +130,24 @@ pub struct WvgParser<'a> {
     elements: Vec<WvgElement>,
     /// Current element index.
     element_index: usize,
+    /// Current group nesting depth.
+    group_depth: usize,
+    /// Index and raw type index of the element being parsed, if any.
+    current_element: Option<(usize, Option<u32>)>,
 }
 
 impl<'a> WvgParser<'a> {
+    /// Creates a new parser with default options.
     pub fn new(bs: &'a mut BitStream<'a>) -> Self {
+        Self::with_options(bs, ParseOptions::default())
+    }
+
+    /// Creates a new parser with the given options.
+    pub fn with_options(bs: &'a mut BitStream<'a>, options: ParseOptions) -> Self {
         Self {
             bs,
+            options,
+            palette: Vec::new(),
             element_masks: Vec::new(),
             attribute_masks: AttributeMasks::default(),
             generic_params: GenericParams::default(),
@@ -48,9 +157,21 @@ impl<'a> WvgParser<'a> {
             offset_y_use: false,
             elements: Vec::new(),
             element_index: 0,
+            group_depth: 0,
+            current_element: None,
         }
     }
 
+    /// Sets a palette for the palette color schemes, for files that rely on
+    /// an external or shared palette.
+    ///
+    /// Draw colors of the `Rgb6BitPalette` and `WebsafePalette` schemes are
+    /// looked up in this palette. A palette embedded in the file replaces it.
+    pub fn with_palette(mut self, palette: Vec<Color>) -> Self {
+        self.palette = palette;
+        self
+    }
+
     /// Parses the WVG data and returns a structured document.
     ///
     /// # Errors
@@ -58,32 +179,254 @@ impl<'a> WvgParser<'a> {
     /// Returns an error if:
     /// - The data is malformed
     /// - An unsupported feature is encountered
-    /// - End of stream is reached unexpectedly
+    /// - The data is a Character Size WVG (`WvgError::CharacterSizeWvg`, which
+    ///   carries its decoded header)
+    /// - End of stream is reached unexpectedly, including for empty input
+    ///
+    /// With `ParseOptions::element_context` set, errors raised inside an
+    /// element are wrapped in `WvgError::ElementError`.
     pub fn parse(mut self) -> WvgResult<WvgDocument> {
-        let wvg_type = self.bs.read_bit()?;
+        self.parse_borrowed()
+    }
+
+    /// Parses like `parse`, but leaves the parser usable afterwards.
+    ///
+    /// After an error, `elements`, `element_masks` and `position` describe
+    /// the state the parser reached, e.g. for recovery tools. On success the
+    /// elements are moved into the returned document.
+    ///
+    /// # Errors
+    ///
+    /// Same as `parse`.
+    pub fn parse_borrowed(&mut self) -> WvgResult<WvgDocument> {
+        let wvg_type = self.read_wvg_type()?;
 
         if wvg_type == 0 {
             info!("Parsing Character Size WVG");
-            return Err(WvgError::UnsupportedFeature(UnsupportedFeature::CharacterSizeWvg));
+            let header = self.parse_character_size_header()?;
+            return Err(WvgError::CharacterSizeWvg(Box::new(header)));
         }
 
         info!("Parsing Standard WVG");
         let header = self.parse_standard_wvg_header()?;
-        self.parse_elements()?;
+        if let Err(e) = self.parse_elements() {
+            return Err(self.with_element_context(e));
+        }
 
         Ok(WvgDocument {
             header,
-            elements: self.elements,
+            elements: std::mem::take(&mut self.elements),
         })
     }
 
-    fn parse_standard_wvg_header(&mut self) -> WvgResult<WvgHeader> {
-        debug!("--- Header ---");
+    /// Parses only as far as the color configuration and returns it,
+    /// including any embedded palette.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is empty, or the general information or
+    /// color configuration is malformed or truncated.
+    pub fn parse_color_config_only(mut self) -> WvgResult<ColorConfig> {
+        // Both formats start with the general information and colors
+        self.read_wvg_type()?;
+
+        self.parse_general_info()?;
+        self.parse_color_configuration()
+    }
 
+    /// Parses the header of a Character Size WVG, after the WVG type bit.
+    ///
+    /// See `CharacterSizeHeader` for which fields are decoded.
+    fn parse_character_size_header(&mut self) -> WvgResult<CharacterSizeHeader> {
         let general_info = self.parse_general_info()?;
         let color_config = self.parse_color_configuration()?;
+
+        let remaining_bit_len = self.bs.remaining_bits();
+        let mut remaining_bits = Vec::with_capacity(remaining_bit_len.div_ceil(8));
+        let mut left = remaining_bit_len;
+        while left > 0 {
+            let n = left.min(8);
+            let byte = self.bs.read_bits(n as u8)? << (8 - n);
+            remaining_bits.push(byte as u8);
+            left -= n;
+        }
+        debug!("Character Size WVG: {} undecoded header bits", remaining_bit_len);
+
+        Ok(CharacterSizeHeader {
+            general_info,
+            color_config,
+            remaining_bits,
+            remaining_bit_len,
+        })
+    }
+
+    /// Returns the elements parsed so far.
+    pub fn elements(&self) -> &[WvgElement] {
+        &self.elements
+    }
+
+    /// Returns the element masks read from the header, or an empty slice if
+    /// they have not been read yet.
+    pub fn element_masks(&self) -> &[bool] {
+        &self.element_masks
+    }
+
+    /// Returns the current position in the bit stream.
+    pub fn position(&self) -> BitPos {
+        self.bs.checkpoint()
+    }
+
+    /// Wraps `error` in `WvgError::ElementError` if it was raised inside an
+    /// element and element context was requested.
+    fn with_element_context(&self, error: WvgError) -> WvgError {
+        match self.current_element {
+            Some((index, raw_type)) if self.options.element_context => WvgError::ElementError {
+                index,
+                raw_type,
+                source: Box::new(error),
+            },
+            _ => error,
+        }
+    }
+
+    /// Runs `parse` as the given stage, wrapping its error in
+    /// `WvgError::StageError` if stage context was requested.
+    fn staged<T>(
+        &mut self,
+        stage: ParseStage,
+        parse: impl FnOnce(&mut Self) -> WvgResult<T>,
+    ) -> WvgResult<T> {
+        parse(self).map_err(|error| {
+            if self.options.stage_context {
+                WvgError::StageError {
+                    stage,
+                    source: Box::new(error),
+                }
+            } else {
+                error
+            }
+        })
+    }
+
+    /// Parses a container holding several WVG documents.
+    ///
+    /// The container framing is assumed to be a big-endian `u16` document
+    /// count, followed by each document as a big-endian `u32` byte length and
+    /// that many bytes of WVG data. Plain WVG files carry no framing and are
+    /// parsed with `parse`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EndOfStream` if the framing is truncated, or the first error
+    /// raised while parsing a contained document.
+    pub fn parse_many(data: &[u8]) -> WvgResult<Vec<WvgDocument>> {
+        Self::parse_many_with(data, ParseOptions::default())
+    }
+
+    /// Parses a container like `parse_many`, parsing each contained document
+    /// with `options`.
+    ///
+    /// # Errors
+    ///
+    /// Same as `parse_many`.
+    pub fn parse_many_with(data: &[u8], options: ParseOptions) -> WvgResult<Vec<WvgDocument>> {
+        let (count, mut rest) = data.split_first_chunk::<2>().ok_or(WvgError::EndOfStream)?;
+        let count = u16::from_be_bytes(*count);
+        debug!("Container with {} documents", count);
+
+        // The count is untrusted, so grow the vector as documents are parsed
+        let mut documents = Vec::new();
+        for i in 0..count {
+            let (length, tail) = rest.split_first_chunk::<4>().ok_or(WvgError::EndOfStream)?;
+            let length = u32::from_be_bytes(*length) as usize;
+            if tail.len() < length {
+                return Err(WvgError::EndOfStream);
+            }
+            let (payload, tail) = tail.split_at(length);
+            trace!("Container document {}: {} bytes", i, length);
+
+            let mut bs = BitStream::new(payload);
+            documents.push(WvgParser::with_options(&mut bs, options.clone()).parse()?);
+            rest = tail;
+        }
+
+        Ok(documents)
+    }
+
+    /// Scans the header of `data` and reports which features it declares.
+    ///
+    /// Only the header is read; element bodies are not parsed, so this
+    /// succeeds for files using unsupported element types or compact
+    /// coordinates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header itself is malformed or truncated.
+    pub fn scan_features(data: &[u8]) -> WvgResult<FeatureReport> {
+        let mut bs = BitStream::new(data);
+        let mut parser = WvgParser::new(&mut bs);
+        let mut report = FeatureReport::default();
+
+        if parser.read_wvg_type()? == 0 {
+            report.character_size = true;
+            return Ok(report);
+        }
+
+        parser.parse_general_info()?;
+        parser.parse_color_configuration()?;
+        parser.parse_element_mask()?;
+        parser.parse_attribute_mask()?;
+        parser.parse_generic_parameters()?;
+
+        report.element_types = parser
+            .element_masks
+            .iter()
+            .enumerate()
+            .filter(|&(_, &enabled)| enabled)
+            .filter_map(|(i, _)| ElementType::from_mask_index(i))
+            .collect();
+
+        report.compact_coordinates = parser.bs.read_bit()? == 1;
+        if !report.compact_coordinates {
+            parser.parse_flat_coordinate_parameters()?;
+            report.animation_mode = parser.parse_animation_settings()?;
+        }
+
+        Ok(report)
+    }
+
+    fn parse_standard_wvg_header(&mut self) -> WvgResult<WvgHeader> {
+        debug!("--- Header ---");
+
+        let general_info = self.staged(ParseStage::GeneralInfo, Self::parse_general_info)?;
+        let color_config =
+            self.staged(ParseStage::ColorConfiguration, Self::parse_color_configuration)?;
         let (codec_params, animation_mode) = self.parse_codec_parameters()?;
 
+        // The element count follows the header, so peek at it for the summary
+        let checkpoint = self.bs.checkpoint();
+        let element_count = self.read_element_count().ok();
+        self.bs.restore(checkpoint);
+
+        let (width, height, coordinate_mode) = match &codec_params.coord_params {
+            CoordinateParams::Flat(params) => {
+                (Some(params.drawing_width), Some(params.drawing_height), "flat")
+            }
+            CoordinateParams::Compact(_) => (None, None, "compact"),
+        };
+        info!(
+            version = general_info.version,
+            width,
+            height,
+            scheme = ?color_config.scheme,
+            palette_size = color_config.palette.len(),
+            coordinate_mode,
+            element_types = codec_params.element_masks.iter().filter(|&&m| m).count(),
+            animation_mode = ?animation_mode,
+            element_count,
+            "WVG header"
+        );
+
         Ok(WvgHeader {
             general_info,
             color_config,
@@ -92,9 +435,21 @@ impl<'a> WvgParser<'a> {
         })
     }
 
+    /// Reads the WVG type bit that starts every file.
+    ///
+    /// Empty input fails with `EndOfStream` like any other truncation, but
+    /// logs that the data was empty rather than cut off.
+    fn read_wvg_type(&mut self) -> WvgResult<u8> {
+        if self.bs.is_empty() {
+            warn!("Input is empty; expected at least the WVG type bit");
+            return Err(WvgError::EndOfStream);
+        }
+        self.bs.read_bit()
+    }
+
     /// Parses general information from the header.
     fn parse_general_info(&mut self) -> WvgResult<GeneralInfo> {
-        let version = self.bs.read_bits(4)? as u8;
+        let version = self.bs.read_bits_u8(4)?;
         info!("Version: {}", version);
 
         let mut info = GeneralInfo {
@@ -128,30 +483,38 @@ impl<'a> WvgParser<'a> {
 
     /// Parses an optional string (author or title).
     ///
-    /// Note: This is not fully implemented, string decoding is currently skipped
-    /// (raw character bits are consumed elsewhere and an empty string returned).
-    /// Proper GSM 7-bit and UCS-2 decoding should be implemented here.
+    /// The length is a fixed `STRING_LENGTH_BITS`-bit count of characters
+    /// (16-bit units for UCS-2) in both text code modes, with no continuation
+    /// scheme, so a string holds at most `MAX_STRING_LENGTH` characters.
+    ///
+    /// UCS-2 strings are decoded with `decode_ucs2`. GSM 7-bit decoding is not
+    /// implemented yet: its characters are skipped and an empty string is
+    /// returned.
     fn parse_optional_string(&mut self, text_code_mode: TextCodeMode) -> WvgResult<Option<String>> {
         let has_string = self.bs.read_bit()?;
         if has_string == 0 {
             return Ok(None);
         }
 
-        let length = self.bs.read_bits(8)? as usize;
+        let length = self.bs.read_bits(STRING_LENGTH_BITS)? as usize;
         debug!("String length: {}", length);
 
-        let char_bits = match text_code_mode {
-            TextCodeMode::Ucs2 => 16,
-            TextCodeMode::Gsm7Bit => 7,
-        };
-
-        // Skip characters for now (string handling is complex)
-        for _ in 0..length {
-            self.bs.read_bits(char_bits)?;
+        match text_code_mode {
+            TextCodeMode::Ucs2 => {
+                let mut units = Vec::with_capacity(length);
+                for _ in 0..length {
+                    units.push(self.bs.read_bits_u16(16)?);
+                }
+                Ok(Some(decode_ucs2(&units, self.options.strict_ucs2)))
+            }
+            TextCodeMode::Gsm7Bit => {
+                // TODO: Decode the GSM 7-bit default alphabet
+                for _ in 0..length {
+                    self.bs.read_bits(7)?;
+                }
+                Ok(Some(String::new()))
+            }
         }
-
-        // TODO: Actually decode the string
-        Ok(Some(String::new()))
     }
 
     fn parse_timestamp(&mut self) -> WvgResult<Option<Timestamp>> {
@@ -161,33 +524,60 @@ impl<'a> WvgParser<'a> {
         }
 
         let year = self.bs.read_signed_bits(13)? as i16;
-        let month = self.bs.read_bits(4)? as u8;
-        let day = self.bs.read_bits(5)? as u8;
-        let hour = self.bs.read_bits(5)? as u8;
-        let minute = self.bs.read_bits(6)? as u8;
-        let second = self.bs.read_bits(6)? as u8;
+        let month = self.bs.read_bits_u8(4)?;
+        let day = self.bs.read_bits_u8(5)?;
+        let hour = self.bs.read_bits_u8(5)?;
+        let minute = self.bs.read_bits_u8(6)?;
+        let second = self.bs.read_bits_u8(6)?;
 
         info!(
             "Timestamp: {}-{:02}-{:02} {:02}:{:02}:{:02}",
             year, month, day, hour, minute, second
         );
 
-        Ok(Some(Timestamp {
+        let timestamp = Timestamp {
             year,
             month,
             day,
             hour,
             minute,
             second,
-        }))
+        };
+
+        if timestamp.is_valid() {
+            return Ok(Some(timestamp));
+        }
+
+        if self.options.strict {
+            return Err(WvgError::ParseError("invalid timestamp".to_string()));
+        }
+
+        let clamped = timestamp.clamped();
+        warn!(
+            "Invalid timestamp, clamped to {}-{:02}-{:02} {:02}:{:02}:{:02}",
+            clamped.year, clamped.month, clamped.day, clamped.hour, clamped.minute, clamped.second
+        );
+        Ok(Some(clamped))
     }
 
     fn parse_color_configuration(&mut self) -> WvgResult<ColorConfig> {
         let scheme = self.parse_color_scheme()?;
         info!("Color Scheme: {:?}", scheme);
 
+        // An embedded palette directly follows the scheme code
+        match scheme {
+            ColorScheme::Rgb6BitPalette => self.palette = self.parse_6bit_palette()?,
+            ColorScheme::WebsafePalette => self.palette = self.parse_8bit_palette()?,
+            _ => {}
+        }
+
+        let uses_palette = matches!(
+            scheme,
+            ColorScheme::Rgb6BitPalette | ColorScheme::WebsafePalette
+        );
         let mut config = ColorConfig {
             scheme,
+            palette: if uses_palette { self.palette.clone() } else { Vec::new() },
             ..Default::default()
         };
 
@@ -214,6 +604,8 @@ impl<'a> WvgParser<'a> {
         Ok(config)
     }
 
+    /// Reads the prefix code of the color scheme. The palette of the palette
+    /// schemes is left to `parse_color_configuration`.
     fn parse_color_scheme(&mut self) -> WvgResult<ColorScheme> {
         let b1 = self.bs.read_bit()?;
         if b1 == 0 {
@@ -245,22 +637,19 @@ impl<'a> WvgParser<'a> {
         let suffix = (b3 << 1) | b4;
 
         match suffix {
-            0 => {
-                // 6-bit RGB with palette
-                self.parse_6bit_palette()?;
-                Ok(ColorScheme::Rgb6BitPalette)
-            }
-            1 => {
-                // Websafe with palette
-                self.parse_8bit_palette()?;
-                Ok(ColorScheme::WebsafePalette)
-            }
+            0 => Ok(ColorScheme::Rgb6BitPalette),
+            1 => Ok(ColorScheme::WebsafePalette),
             2 => Ok(ColorScheme::Rgb12Bit),
             3 => Ok(ColorScheme::Rgb24Bit),
-            _ => unreachable!(),
+            _ => Err(WvgError::InvalidColorScheme(format!("11{:02b}", suffix))),
         }
     }
 
+    /// Reads a palette of 6-bit RGB colors.
+    ///
+    /// The 5-bit count field stores the size minus one, so a palette holds 1
+    /// to 32 colors. The format has a single palette shared by line, fill and
+    /// background colors, and no extension for larger palettes.
     fn parse_6bit_palette(&mut self) -> WvgResult<Vec<Color>> {
         let num_colors = self.bs.read_bits(5)? as usize + 1;
         debug!("6-bit Palette: {} colors", num_colors);
@@ -268,14 +657,15 @@ impl<'a> WvgParser<'a> {
         let mut palette = Vec::with_capacity(num_colors);
         for _ in 0..num_colors {
             let rgb = self.bs.read_bits(6)?;
-            let r = (((rgb >> 4) & 0x3) * 85) as u8;
-            let g = (((rgb >> 2) & 0x3) * 85) as u8;
-            let b = ((rgb & 0x3) * 85) as u8;
-            palette.push(Color::new(r, g, b));
+            palette.push(rgb6_color(rgb));
         }
         Ok(palette)
     }
 
+    /// Reads a palette of websafe color indices.
+    ///
+    /// The 7-bit count field stores the size minus one, so a palette holds 1
+    /// to 128 of the 256 websafe colors.
     fn parse_8bit_palette(&mut self) -> WvgResult<Vec<Color>> {
         let num_colors = self.bs.read_bits(7)? as usize + 1;
         debug!("8-bit Palette: {} colors", num_colors);
@@ -289,10 +679,11 @@ impl<'a> WvgParser<'a> {
     }
 
     /// Parses a draw color based on the color scheme.
-    /// 
-    /// Note: This function currently does not handle palette lookups for
-    /// `Rgb6BitPalette` and `WebsafePalette` schemes. It returns black as a
-    /// placeholder in those cases.    
+    ///
+    /// For the palette schemes the color is an index into the palette, using
+    /// just enough bits to address every palette entry. An index past the end
+    /// of a palette whose size is not a power of two is an
+    /// `InvalidColorIndex` error.
     fn parse_draw_color(&mut self, scheme: ColorScheme) -> WvgResult<Color> {
         match scheme {
             ColorScheme::BlackAndWhite => {
@@ -300,26 +691,21 @@ impl<'a> WvgParser<'a> {
                 Ok(if bit == 1 { Color::BLACK } else { Color::WHITE })
             }
             ColorScheme::Grayscale2Bit => {
-                let val = self.bs.read_bits(2)?;
-                let gray = (val * 85) as u8;
+                let gray = expand_2bit(self.bs.read_bits(2)?);
                 Ok(Color::new(gray, gray, gray))
             }
             ColorScheme::Predefined2Bit => {
-                let val = self.bs.read_bits(2)?;
-                Ok(match val {
+                // A 2-bit value always names one of the four colors
+                Ok(match self.bs.read_bits(2)? {
                     0 => Color::WHITE,
                     1 => Color::new(255, 0, 0), // Red
                     2 => Color::new(0, 255, 0), // Green
-                    3 => Color::new(0, 0, 255), // Blue
-                    _ => unreachable!(),
+                    _ => Color::new(0, 0, 255), // Blue
                 })
             }
             ColorScheme::Rgb6Bit => {
                 let rgb = self.bs.read_bits(6)?;
-                let r = (((rgb >> 4) & 0x3) * 85) as u8;
-                let g = (((rgb >> 2) & 0x3) * 85) as u8;
-                let b = ((rgb & 0x3) * 85) as u8;
-                Ok(Color::new(r, g, b))
+                Ok(rgb6_color(rgb))
             }
             ColorScheme::Websafe => {
                 let index = self.bs.read_bits(8)? as usize;
@@ -327,21 +713,26 @@ impl<'a> WvgParser<'a> {
             }
             ColorScheme::Rgb12Bit => {
                 let rgb = self.bs.read_bits(12)?;
-                let r = (((rgb >> 8) & 0xF) * 17) as u8;
-                let g = (((rgb >> 4) & 0xF) * 17) as u8;
-                let b = ((rgb & 0xF) * 17) as u8;
+                let r = expand_4bit(rgb >> 8);
+                let g = expand_4bit(rgb >> 4);
+                let b = expand_4bit(rgb);
                 Ok(Color::new(r, g, b))
             }
             ColorScheme::Rgb24Bit => {
-                let r = self.bs.read_bits(8)? as u8;
-                let g = self.bs.read_bits(8)? as u8;
-                let b = self.bs.read_bits(8)? as u8;
+                let r = self.bs.read_bits_u8(8)?;
+                let g = self.bs.read_bits_u8(8)?;
+                let b = self.bs.read_bits_u8(8)?;
                 Ok(Color::new(r, g, b))
             }
             ColorScheme::Rgb6BitPalette | ColorScheme::WebsafePalette => {
-                // TODO: Implement palette lookup
-                warn!("Palette color lookup not fully implemented");
-                Ok(Color::BLACK)
+                let index = self.bs.read_bits(palette_index_bits(self.palette.len()))?;
+                self.palette
+                    .get(index as usize)
+                    .copied()
+                    .ok_or(WvgError::InvalidColorIndex {
+                        index,
+                        palette_len: self.palette.len(),
+                    })
             }
         }
     }
@@ -349,11 +740,13 @@ impl<'a> WvgParser<'a> {
     fn parse_codec_parameters(&mut self) -> WvgResult<(CodecParams, Option<AnimationMode>)> {
         debug!("Parsing Codec Parameters...");
 
-        self.parse_element_mask()?;
-        self.parse_attribute_mask()?;
-        self.parse_generic_parameters()?;
-        let coord_params = self.parse_coordinate_parameters()?;
-        let animation_mode = self.parse_animation_settings()?;
+        self.staged(ParseStage::ElementMasks, Self::parse_element_mask)?;
+        self.staged(ParseStage::AttributeMasks, Self::parse_attribute_mask)?;
+        self.staged(ParseStage::GenericParameters, Self::parse_generic_parameters)?;
+        let coord_params =
+            self.staged(ParseStage::CoordinateParameters, Self::parse_coordinate_parameters)?;
+        let animation_mode =
+            self.staged(ParseStage::AnimationSettings, Self::parse_animation_settings)?;
 
         Ok((
             CodecParams {
@@ -366,6 +759,12 @@ impl<'a> WvgParser<'a> {
         ))
     }
 
+    /// Reads the element masks: 8 base masks, an extension bit, and the 5
+    /// extended masks (polygon onwards) only if the extension bit is set.
+    ///
+    /// Element type indices are mapped onto the enabled masks only, so without
+    /// the extension bit element types 8-12 can never be selected and an index
+    /// beyond the enabled types is rejected with `InvalidElementType`.
     fn parse_element_mask(&mut self) -> WvgResult<()> {
         let mut masks = Vec::new();
 
@@ -408,8 +807,8 @@ impl<'a> WvgParser<'a> {
     fn parse_generic_parameters(&mut self) -> WvgResult<()> {
         // Angle parameters
         if self.bs.read_bit()? == 1 {
-            self.generic_params.angle_resolution = self.bs.read_bits(2)? as u8;
-            self.generic_params.angle_in_bits = self.bs.read_bits(3)? as u8;
+            self.generic_params.angle_resolution = self.bs.read_bits_u8(2)?;
+            self.generic_params.angle_in_bits = self.bs.read_bits_u8(3)?;
             debug!(
                 "Generic: Angle Res={}, Bits={}",
                 self.generic_params.angle_resolution, self.generic_params.angle_in_bits
@@ -420,8 +819,8 @@ impl<'a> WvgParser<'a> {
 
         // Scale parameters
         if self.bs.read_bit()? == 1 {
-            self.generic_params.scale_resolution = self.bs.read_bits(2)? as u8;
-            self.generic_params.scale_in_bits = self.bs.read_bits(4)? as u8;
+            self.generic_params.scale_resolution = self.bs.read_bits_u8(2)?;
+            self.generic_params.scale_in_bits = self.bs.read_bits_u8(4)?;
             debug!(
                 "Generic: Scale Res={}, Bits={}",
                 self.generic_params.scale_resolution, self.generic_params.scale_in_bits
@@ -432,7 +831,7 @@ impl<'a> WvgParser<'a> {
 
         // Index parameters
         if self.bs.read_bit()? == 1 {
-            self.generic_params.index_in_bits = self.bs.read_bits(4)? as u8;
+            self.generic_params.index_in_bits = self.bs.read_bits_u8(4)?;
             debug!("Generic: Index Bits={}", self.generic_params.index_in_bits);
         } else {
             debug!("Generic: Index Bits Default (2 -> 3 bits)");
@@ -443,7 +842,7 @@ impl<'a> WvgParser<'a> {
         let has_polygon = self.element_masks.get(8).copied().unwrap_or(false);
 
         if has_circular || has_polygon {
-            self.generic_params.curve_offset_in_bits = Some(self.bs.read_bit()? as u8);
+            self.generic_params.curve_offset_in_bits = Some(self.bs.read_bit()?);
             debug!(
                 "Generic: Curve Offset Bits={}",
                 self.generic_params.curve_offset_in_bits.unwrap()
@@ -470,25 +869,25 @@ impl<'a> WvgParser<'a> {
     }
 
     fn parse_flat_coordinate_parameters(&mut self) -> WvgResult<FlatCoordinateParams> {
-        let drawing_width = self.bs.read_bits(16)? as u16;
+        let drawing_width = self.bs.read_bits_u16(16)?;
         info!("Drawing Width: {}", drawing_width);
 
         let drawing_height = if self.bs.read_bit()? == 1 {
-            self.bs.read_bits(16)? as u16
+            self.bs.read_bits_u16(16)?
         } else {
             drawing_width
         };
         info!("Drawing Height: {}", drawing_height);
 
-        let max_x_in_bits = self.bs.read_bits(4)? as u8;
-        let max_y_in_bits = self.bs.read_bits(4)? as u8;
+        let max_x_in_bits = self.bs.read_bits_u8(4)?;
+        let max_y_in_bits = self.bs.read_bits_u8(4)?;
         let xy_all_positive = self.bs.read_bit()? == 1;
-        let trans_xy_in_bits = self.bs.read_bits(4)? as u8;
-        let num_points_in_bits = self.bs.read_bits(4)? as u8;
-        let offset_x_in_bits_level1 = self.bs.read_bits(4)? as u8;
-        let offset_y_in_bits_level1 = self.bs.read_bits(4)? as u8;
-        let offset_x_in_bits_level2 = self.bs.read_bits(4)? as u8;
-        let offset_y_in_bits_level2 = self.bs.read_bits(4)? as u8;
+        let trans_xy_in_bits = self.bs.read_bits_u8(4)?;
+        let num_points_in_bits = self.bs.read_bits_u8(4)?;
+        let offset_x_in_bits_level1 = self.bs.read_bits_u8(4)?;
+        let offset_y_in_bits_level1 = self.bs.read_bits_u8(4)?;
+        let offset_x_in_bits_level2 = self.bs.read_bits_u8(4)?;
+        let offset_y_in_bits_level2 = self.bs.read_bits_u8(4)?;
 
         debug!(
             "Flat Params: MaxX={}, MaxY={}, AllPos={}, TransXY={}",
@@ -503,7 +902,7 @@ impl<'a> WvgParser<'a> {
             offset_x_in_bits_level2, offset_y_in_bits_level2
         );
 
-        Ok(FlatCoordinateParams {
+        let params = FlatCoordinateParams {
             drawing_width,
             drawing_height,
             max_x_in_bits,
@@ -515,7 +914,10 @@ impl<'a> WvgParser<'a> {
             offset_y_in_bits_level1,
             offset_x_in_bits_level2,
             offset_y_in_bits_level2,
-        })
+        };
+        params.validate()?;
+
+        Ok(params)
     }
 
     fn parse_animation_settings(&mut self) -> WvgResult<Option<AnimationMode>> {
@@ -533,41 +935,100 @@ impl<'a> WvgParser<'a> {
         Ok(None)
     }
 
+    /// Reads the number of elements, stored in 7 bits or, after a set flag
+    /// bit, in 15 bits.
+    fn read_element_count(&mut self) -> WvgResult<usize> {
+        let wide = self.bs.read_bit()? == 1;
+        Ok(self.bs.read_bits(if wide { 15 } else { 7 })? as usize)
+    }
+
+    /// Returns the number of bits used for the element type, based on how
+    /// many element types are enabled.
+    fn element_type_bits(&self) -> u8 {
+        let ones_count: usize = self.element_masks.iter().filter(|&&x| x).count();
+        match ones_count {
+            // A single enabled type is implied, so no type bits are stored
+            0 | 1 => 0,
+            2 => 1,
+            3 | 4 => 2,
+            5..=8 => 3,
+            _ => 4,
+        }
+    }
+
     fn parse_elements(&mut self) -> WvgResult<()> {
         debug!("--- Elements ---");
 
-        // Parse number of elements
-        let num_elements = if self.bs.read_bit()? == 0 {
-            self.bs.read_bits(7)? as usize
+        let num_elements = self.staged(ParseStage::ElementCount, Self::read_element_count)?;
+        info!("Number of elements: {}", num_elements);
+
+        if self.options.read_until_eof {
+            self.parse_elements_until_eof()?;
+            if self.elements.len() != num_elements {
+                info!(
+                    "Read {} elements, {} declared",
+                    self.elements.len(),
+                    num_elements
+                );
+            }
         } else {
-            self.bs.read_bits(15)? as usize
-        };
+            // Every element takes at least its type bits, and at least one
+            // bit overall, so reject counts the remaining data cannot hold
+            let min_bits = usize::from(self.element_type_bits().max(1));
+            let limit = self.bs.remaining_bits() / min_bits;
+            if num_elements > limit {
+                return Err(WvgError::LimitExceeded {
+                    what: "element count",
+                    value: num_elements,
+                    limit,
+                });
+            }
 
-        info!("Number of elements: {}", num_elements);
+            self.elements.reserve(num_elements);
+            for _ in 0..num_elements {
+                self.parse_element()?;
+            }
+        }
 
-        for _ in 0..num_elements {
-            self.parse_element()?;
+        if self.group_depth != 0 {
+            if self.options.strict {
+                return Err(WvgError::ParseError("unbalanced group".to_string()));
+            }
+            warn!("{} group(s) not closed at end of elements", self.group_depth);
         }
 
         Ok(())
     }
 
+    /// Parses elements until only padding remains, ignoring the declared count.
+    ///
+    /// Running out of data in the middle of an element is treated as the end
+    /// of the element list; the incomplete element is discarded.
+    fn parse_elements_until_eof(&mut self) -> WvgResult<()> {
+        while !self.bs.only_padding_remains() {
+            match self.parse_element() {
+                Ok(()) => {}
+                Err(WvgError::EndOfStream) => {
+                    debug!("Reached end of stream inside element, stopping");
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
     fn parse_element(&mut self) -> WvgResult<()> {
-        // Calculate number of bits needed for element type based on mask count
-        let ones_count: usize = self.element_masks.iter().filter(|&&x| x).count();
-        let bits = match ones_count {
-            0 | 1 => 0,
-            2 => 1,
-            3 | 4 => 2,
-            5..=8 => 3,
-            _ => 4,
-        };
+        self.current_element = Some((self.element_index, None));
+        let start = self.bs.byte_position();
 
+        let bits = self.element_type_bits();
         let elem_type_idx = if bits > 0 {
             self.bs.read_bits(bits)?
         } else {
             0
         };
+        self.current_element = Some((self.element_index, Some(elem_type_idx)));
 
         // Map element type index to actual type based on mask order
         let mut current_idx = 0u32;
@@ -583,9 +1044,7 @@ impl<'a> WvgParser<'a> {
             }
         }
 
-        let actual_type = actual_type.ok_or_else(|| {
-            WvgError::InvalidElementType(elem_type_idx)
-        })?;
+        let actual_type = actual_type.ok_or(WvgError::InvalidElementType(elem_type_idx))?;
 
         trace!("Element Type Index: {}, Actual Type: {}", elem_type_idx, actual_type);
 
@@ -636,7 +1095,8 @@ impl<'a> WvgParser<'a> {
             }
             9 => {
                 // Special Shape
-                return Err(WvgError::UnsupportedFeature(UnsupportedFeature::SpecialShape));
+                trace!("Parsing Special Shape Element");
+                self.parse_special_shape_element()?
             }
             10 => {
                 // Frame
@@ -647,7 +1107,9 @@ impl<'a> WvgParser<'a> {
                 return Err(WvgError::UnsupportedFeature(UnsupportedFeature::TextElement));
             }
             12 => {
-                // Extended
+                // Extended. Skipping these needs the width and unit (bits or
+                // bytes) of their length field; guessing wrong would misalign
+                // every following element, so they are rejected instead.
                 return Err(WvgError::UnsupportedFeature(UnsupportedFeature::ExtendedElement));
             }
             _ => {
@@ -655,10 +1117,19 @@ impl<'a> WvgParser<'a> {
             }
         };
 
+        let span = if self.options.track_spans {
+            let end = self.bs.byte_position() + usize::from(self.bs.bit_position() > 0);
+            Some((start, end))
+        } else {
+            None
+        };
+
         self.elements.push(WvgElement {
             id: element_id,
+            span,
             data: element_data,
         });
+        self.current_element = None;
 
         Ok(())
     }
@@ -674,7 +1145,15 @@ impl<'a> WvgParser<'a> {
         self.offset_x_use = self.bs.read_bit()? == 1;
         self.offset_y_use = self.bs.read_bit()? == 1;
 
-        // Check if any attribute mask is set
+        self.parse_optional_attributes_set()
+    }
+
+    /// Parses an attribute set preceded by its presence bit.
+    fn parse_optional_attributes_set(&mut self) -> WvgResult<ElementAttributes> {
+        // <Attribute Set Presence> is conditional on the header's attribute
+        // masks: with all four masks off there is no attribute set to
+        // announce, so not even the presence bit is written. The sample data
+        // relies on this, as its masks are all off.
         let has_any_attr = self.attribute_masks.line_type
             || self.attribute_masks.line_width
             || self.attribute_masks.line_color
@@ -708,7 +1187,8 @@ impl<'a> WvgParser<'a> {
         }
 
         if self.attribute_masks.line_color {
-            // Only read line color if line width is not zero
+            // Only read line color if line width is not zero. The flag bit is
+            // absent entirely in that case, so the fill bits follow directly.
             let line_width = attrs.line_width.unwrap_or(LineWidth::Fine);
             if !matches!(line_width, LineWidth::None) && self.bs.read_bit()? == 1 {
                 // TODO: Parse actual color
@@ -733,11 +1213,22 @@ impl<'a> WvgParser<'a> {
         Ok(attrs)
     }
 
+    /// Parses a polyline element.
+    ///
+    /// Grammar as implemented:
+    ///
+    /// ```text
+    /// <Polyline> ::= <BasicElementHeader> <NumPoints> <Point> <Offset>*
+    /// ```
+    ///
+    /// Only the first point is absolute. There is no per-point absolute flag
+    /// or sub-path break, so a polyline is always one connected line; a
+    /// disjoint shape takes several elements (or a group).
     fn parse_polyline_element(&mut self) -> WvgResult<ElementData> {
         let attributes = self.parse_basic_element_header()?;
         let mut points = Vec::new();
 
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
         let num_points = self.bs.read_bits(params.num_points_in_bits)? as usize;
         trace!("Polyline Points: {}", num_points);
 
@@ -749,20 +1240,31 @@ impl<'a> WvgParser<'a> {
         for _ in 0..num_points {
             let (dx, dy) = self.parse_offset()?;
             let last = points.last().unwrap();
-            points.push(Point::new(last.x + dx, last.y + dy));
+            let point = last
+                .checked_offset(dx, dy)
+                .ok_or(WvgError::CoordinateOverflow)?;
+            points.push(point);
         }
 
         Ok(ElementData::Polyline(PolylineElement { attributes, points }))
     }
 
     fn parse_circular_polyline_element(&mut self) -> WvgResult<ElementData> {
+        // The curve offset width is only present when circular polylines
+        // (or polygons) are enabled in the header
+        if self.generic_params.curve_offset_in_bits.is_none() {
+            return Err(WvgError::ParseError(
+                "circular polyline without curve offset parameter".to_string(),
+            ));
+        }
+
         let attributes = self.parse_basic_element_header()?;
         let mut points = Vec::new();
 
         let curve_hint = self.bs.read_bit()? == 1;
         trace!("Curve Hint: {}", curve_hint);
 
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
         let num_points = self.bs.read_bits(params.num_points_in_bits)? as usize;
         trace!("Circular Polyline Points: {}", num_points);
 
@@ -774,13 +1276,19 @@ impl<'a> WvgParser<'a> {
             is_absolute: true,
         });
 
-        // Second point (absolute) with curve offset
+        // Second point (absolute unless configured otherwise) with curve offset
         let offset = self.parse_curve_offset(curve_hint)?;
-        let second_pt = self.parse_point()?;
+        let relative = self.options.relative_second_point;
+        let second_pt = if relative {
+            let (dx, dy) = self.parse_offset()?;
+            Point::new(dx, dy)
+        } else {
+            self.parse_point()?
+        };
         points.push(CircularPoint {
             curve_offset: offset,
             point: second_pt,
-            is_absolute: true,
+            is_absolute: !relative,
         });
 
         // Subsequent points (relative) with curve offsets
@@ -823,18 +1331,18 @@ impl<'a> WvgParser<'a> {
     }
 
     fn parse_point(&mut self) -> WvgResult<Point> {
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
 
         let x = if params.xy_all_positive {
             self.bs.read_bits(params.max_x_in_bits)? as i32
         } else {
-            self.bs.read_signed_bits(params.max_x_in_bits)?
+            read_signed_coordinate(self.bs, self.options.sign_magnitude, params.max_x_in_bits)?
         };
 
         let y = if params.xy_all_positive {
             self.bs.read_bits(params.max_y_in_bits)? as i32
         } else {
-            self.bs.read_signed_bits(params.max_y_in_bits)?
+            read_signed_coordinate(self.bs, self.options.sign_magnitude, params.max_y_in_bits)?
         };
 
         trace!("Point: ({}, {})", x, y);
@@ -842,7 +1350,7 @@ impl<'a> WvgParser<'a> {
     }
 
     fn parse_offset(&mut self) -> WvgResult<(i32, i32)> {
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
 
         let x_bits = if self.offset_x_use {
             params.offset_x_in_bits_level2
@@ -856,8 +1364,8 @@ impl<'a> WvgParser<'a> {
             params.offset_y_in_bits_level1
         };
 
-        let dx = self.bs.read_signed_bits(x_bits)?;
-        let dy = self.bs.read_signed_bits(y_bits)?;
+        let dx = read_signed_coordinate(self.bs, self.options.sign_magnitude, x_bits)?;
+        let dy = read_signed_coordinate(self.bs, self.options.sign_magnitude, y_bits)?;
 
         trace!("Offset: ({}, {})", dx, dy);
         Ok((dx, dy))
@@ -875,34 +1383,94 @@ impl<'a> WvgParser<'a> {
         // TODO: Parse full shape data
         warn!("Simple shape parsing is incomplete");
 
+        // Until the geometry is parsed, shapes fill a 10x10 placeholder box
         Ok(ElementData::SimpleShape(SimpleShapeElement {
             shape_type,
             attributes,
+            origin: Point::new(0, 0),
+            width: 10,
+            height: 10,
+            rotation: None,
         }))
     }
 
+    /// Parses a special shape element.
+    ///
+    /// ```text
+    /// <SpecialShape> ::= <BasicElementHeader> <ShapeKind> <ShapeData>
+    /// <ShapeKind>    ::= 0 (star) | 1 (grid)
+    /// <Star>         ::= <NumPoints> <Center> <OuterRadius> <InnerRadius>
+    ///                    (0 | (1 <Angle>))
+    /// ```
+    ///
+    /// The point count is `num_points_in_bits` wide, the center is a point
+    /// and the radii are X values, all as declared in the coordinate
+    /// parameters. The rotation uses the transform angle width. Grids are not
+    /// parsed yet and are reported as unsupported.
+    fn parse_special_shape_element(&mut self) -> WvgResult<ElementData> {
+        let attributes = self.parse_basic_element_header()?;
+
+        if self.bs.read_bit()? == 1 {
+            return Err(WvgError::UnsupportedFeature(UnsupportedFeature::SpecialShape));
+        }
+
+        let num_points_bits = require_flat(&self.flat_params)?.num_points_in_bits;
+        let num_points = self.bs.read_bits(num_points_bits)?;
+        let center = self.parse_point()?;
+        let outer_radius = self.parse_x_value()?;
+        let inner_radius = self.parse_x_value()?;
+        let rotation = if self.bs.read_bit()? == 1 {
+            Some(self.parse_angle_value()?)
+        } else {
+            None
+        };
+        trace!(
+            "Star: {} points, radii {}/{}, rotation {:?}",
+            num_points,
+            outer_radius,
+            inner_radius,
+            rotation
+        );
+
+        Ok(ElementData::SpecialShape(SpecialShapeElement {
+            shape: SpecialShape::Star(StarShape {
+                center,
+                num_points,
+                outer_radius,
+                inner_radius,
+                rotation,
+            }),
+            attributes,
+        }))
+    }
+
+    /// Parses a reuse element.
+    ///
+    /// The element index is `index_in_bits + 1` bits wide as declared in the
+    /// generic parameters. Deriving the width from the number of preceding
+    /// elements instead would misread the sample data, whose encoder writes
+    /// 5-bit indices after only 13 elements.
+    ///
+    /// Only preceding elements can be reused; referencing the reuse element
+    /// itself or a later one is rejected with `WvgError::InvalidReuse`.
     fn parse_reuse_element(&mut self) -> WvgResult<ElementData> {
         let idx_bits = self.generic_params.index_in_bits + 1;
-        let mut elem_index = self.bs.read_bits(idx_bits)?;
+        let elem_index = self.bs.read_bits(idx_bits)?;
 
-        // Heuristic fix for potential index issues
         if elem_index as usize >= self.elements.len() {
-            warn!(
-                "Reuse Element Index {} out of bounds (max {}). Masking MSB.",
-                elem_index,
-                self.elements.len().saturating_sub(1)
-            );
-            let masked_index = elem_index & ((1 << (idx_bits - 1)) - 1);
-            if (masked_index as usize) < self.elements.len() {
-                trace!("  -> Corrected to {}", masked_index);
-                elem_index = masked_index;
-            } else {
-                trace!("  -> Masked index {} still out of bounds.", masked_index);
-            }
+            return Err(WvgError::InvalidReuse {
+                index: elem_index,
+                position: self.elements.len(),
+            });
         }
 
         trace!("Reuse Element Index: {}", elem_index);
 
+        // The transform is always present in a reuse element; only its
+        // components are optional, each behind its own presence bit. A bare
+        // reference therefore still spends the TranslateX, TranslateY and
+        // extended-transform flags. The reuse elements in the sample only
+        // decode correctly with this layout.
         let transform = self.parse_transform()?;
 
         // Array parameters
@@ -965,20 +1533,20 @@ impl<'a> WvgParser<'a> {
     }
 
     fn parse_x_value(&mut self) -> WvgResult<i32> {
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
         if params.xy_all_positive {
             Ok(self.bs.read_bits(params.max_x_in_bits)? as i32)
         } else {
-            self.bs.read_signed_bits(params.max_x_in_bits)
+            read_signed_coordinate(self.bs, self.options.sign_magnitude, params.max_x_in_bits)
         }
     }
 
     fn parse_y_value(&mut self) -> WvgResult<i32> {
-        let params = self.flat_params.as_ref().unwrap();
+        let params = require_flat(&self.flat_params)?;
         if params.xy_all_positive {
             Ok(self.bs.read_bits(params.max_y_in_bits)? as i32)
         } else {
-            self.bs.read_signed_bits(params.max_y_in_bits)
+            read_signed_coordinate(self.bs, self.options.sign_magnitude, params.max_y_in_bits)
         }
     }
 
@@ -1025,6 +1593,21 @@ impl<'a> WvgParser<'a> {
         Ok(attrs)
     }
 
+    /// Parses a group start or group end element.
+    ///
+    /// Grammar as implemented:
+    ///
+    /// ```text
+    /// <GroupElement> ::= (0 <GroupStart>) | 1
+    /// <GroupStart>   ::= (0 | (1 <Transform>)) <Display> <AttributeSetPresence>
+    /// ```
+    ///
+    /// The display bit is always present and follows the transform. Reading
+    /// it out of order, or skipping it, misaligns every later element.
+    ///
+    /// The group's attribute defaults follow the display bit, using the same
+    /// mask-controlled presence bit and attribute set as an element header.
+    /// With all attribute masks off, as in the sample data, no bits are read.
     fn parse_group_element(&mut self) -> WvgResult<ElementData> {
         if self.bs.read_bit()? == 0 {
             // Group start
@@ -1035,11 +1618,26 @@ impl<'a> WvgParser<'a> {
                 None
             };
             let display = self.bs.read_bit()? == 1;
-
-            Ok(ElementData::GroupStart(GroupStartElement { transform, display }))
+            let attributes = self.parse_optional_attributes_set()?;
+            self.group_depth += 1;
+
+            Ok(ElementData::GroupStart(GroupStartElement {
+                transform,
+                display,
+                opacity: None,
+                attributes,
+            }))
         } else {
             // Group end
             trace!("Group End");
+            if self.group_depth == 0 {
+                if self.options.strict {
+                    return Err(WvgError::ParseError("unbalanced group".to_string()));
+                }
+                warn!("Group end without matching group start");
+            } else {
+                self.group_depth -= 1;
+            }
             Ok(ElementData::GroupEnd)
         }
     }
@@ -1089,8 +1687,8 @@ impl<'a> WvgParser<'a> {
     }
 
     fn parse_translate_value(&mut self) -> WvgResult<i32> {
-        let params = self.flat_params.as_ref().unwrap();
-        let val = self.bs.read_signed_bits(params.trans_xy_in_bits)?;
+        let params = require_flat(&self.flat_params)?;
+        let val = read_signed_coordinate(self.bs, self.options.sign_magnitude, params.trans_xy_in_bits)?;
         trace!("Translate: {}", val);
         Ok(val)
     }
@@ -1110,6 +1708,72 @@ impl<'a> WvgParser<'a> {
     }
 }
 
+/// Expands a 2-bit channel value to 8 bits.
+///
+/// Multiplying by 85 (`0b01010101`) is identical to replicating the two bits
+/// four times, so 0, 1, 2, 3 map to 0, 85, 170, 255 and both ends of the
+/// range are preserved exactly. Bits above the lowest two are ignored.
+fn expand_2bit(value: u32) -> u8 {
+    ((value & 0x3) * 85) as u8
+}
+
+/// Expands a 4-bit channel value to 8 bits.
+///
+/// Multiplying by 17 (`0x11`) is identical to replicating the nibble, so
+/// `0xA` becomes `0xAA`. Bits above the lowest four are ignored.
+fn expand_4bit(value: u32) -> u8 {
+    ((value & 0xF) * 17) as u8
+}
+
+/// Decodes a 6-bit `RRGGBB` color.
+fn rgb6_color(rgb: u32) -> Color {
+    Color::new(expand_2bit(rgb >> 4), expand_2bit(rgb >> 2), expand_2bit(rgb))
+}
+
+/// Returns the flat coordinate parameters, which are absent in compact mode.
+///
+/// Takes the field rather than `&self` so callers can keep reading from the
+/// bit stream while holding the returned reference.
+fn require_flat(params: &Option<FlatCoordinateParams>) -> WvgResult<&FlatCoordinateParams> {
+    params
+        .as_ref()
+        .ok_or(WvgError::UnsupportedFeature(UnsupportedFeature::CompactCoordinateMode))
+}
+
+/// Reads a signed coordinate as sign-magnitude or two's complement.
+fn read_signed_coordinate(bs: &mut BitStream, sign_magnitude: bool, bits: u8) -> WvgResult<i32> {
+    if sign_magnitude {
+        bs.read_sign_magnitude_bits(bits)
+    } else {
+        bs.read_signed_bits(bits)
+    }
+}
+
+/// Decodes UCS-2 code units into a string.
+///
+/// By default surrogate pairs are combined, so supplementary-plane characters
+/// written as UTF-16 survive. In strict mode every unit is taken as a BMP code
+/// point on its own; since surrogate code points are not valid `char`s, lone
+/// surrogates (and both halves of a pair) become U+FFFD. Unpaired surrogates
+/// are also replaced by U+FFFD in the default mode.
+fn decode_ucs2(units: &[u16], strict: bool) -> String {
+    if strict {
+        units
+            .iter()
+            .map(|&u| char::from_u32(u32::from(u)).unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else {
+        char::decode_utf16(units.iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+}
+
+/// Returns the number of bits needed to index a palette of `len` colors.
+fn palette_index_bits(len: usize) -> u8 {
+    (usize::BITS - len.saturating_sub(1).leading_zeros()) as u8
+}
+
 fn websafe_color(index: usize) -> Color {
     const WEBSAFE_PALETTE: [[u8; 3]; 256] = [
         [255, 255, 255], [255, 204, 255], [255, 153, 255], [255, 102, 255],
@@ -1181,3 +1845,1093 @@ fn websafe_color(index: usize) -> Color {
     let [r, g, b] = WEBSAFE_PALETTE.get(index).copied().unwrap_or([0, 0, 0]);
     Color::new(r, g, b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bit-level writer used to assemble synthetic WVG data.
+    struct BitWriter {
+        bits: Vec<u8>,
+    }
+
+    impl BitWriter {
+        fn new() -> Self {
+            Self { bits: Vec::new() }
+        }
+
+        /// Appends the lowest `n` bits of `value`, MSB first.
+        fn push(&mut self, value: u32, n: u8) -> &mut Self {
+            for i in (0..n).rev() {
+                self.bits.push(((value >> i) & 1) as u8);
+            }
+            self
+        }
+
+        fn into_bytes(self) -> Vec<u8> {
+            self.bits
+                .chunks(8)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u8, |acc, (i, &bit)| acc | (bit << (7 - i)))
+                })
+                .collect()
+        }
+    }
+
+    /// Writes a minimal standard WVG header with the given element masks,
+    /// black-and-white colors, no attributes, and a 128x32 flat canvas.
+    fn header(masks: [bool; 8]) -> BitWriter {
+        header_with_masks(&masks)
+    }
+
+    /// Like `header`, but sets the mask extension bit if more than 8 masks
+    /// are given.
+    fn header_with_masks(masks: &[bool]) -> BitWriter {
+        header_with_attribute_masks(masks, 0)
+    }
+
+    /// Like `header_with_masks`, with the 4 attribute mask bits (line type,
+    /// line width, line color, fill) set to `attribute_masks`.
+    fn header_with_attribute_masks(masks: &[bool], attribute_masks: u32) -> BitWriter {
+        let mut w = BitWriter::new();
+        // Standard WVG, version 0, no extended info
+        w.push(1, 1).push(0, 4).push(0, 1);
+        // Black and white color scheme, no default colors
+        w.push(0b00, 2).push(0b000, 3);
+        // Element masks with extension bit
+        for &mask in &masks[..8] {
+            w.push(mask as u32, 1);
+        }
+        w.push((masks.len() > 8) as u32, 1);
+        for &mask in &masks[8..] {
+            w.push(mask as u32, 1);
+        }
+        // Attribute masks
+        w.push(attribute_masks, 4);
+        // Generic parameters: all defaults
+        w.push(0b000, 3);
+        if masks[2] || masks.get(8).copied().unwrap_or(false) {
+            w.push(0, 1);
+        }
+        // Flat coordinates: 128 wide, same height
+        w.push(0, 1).push(128, 16).push(0, 1);
+        // MaxX=7, MaxY=5, all positive, TransXY=7, NumPoints=4
+        w.push(7, 4).push(5, 4).push(1, 1).push(7, 4).push(4, 4);
+        // Offset bits level 1 and 2
+        w.push(3, 4).push(3, 4).push(5, 4).push(5, 4);
+        if masks[7] {
+            w.push(0, 1);
+        }
+        w
+    }
+
+    /// Decodes a color scheme from the written bits and
+    /// returns it with the total number of bits consumed.
+    fn decode_color_scheme(w: BitWriter) -> (ColorScheme, usize) {
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        let scheme = parser.parse_color_scheme().unwrap();
+        let consumed = parser.bs.byte_position() * 8 + parser.bs.bit_position() as usize;
+        (scheme, consumed)
+    }
+
+    fn prefix(code: u32, n: u8) -> BitWriter {
+        let mut w = BitWriter::new();
+        w.push(code, n);
+        w
+    }
+
+    #[test]
+    fn test_color_scheme_prefixes() {
+        let cases = [
+            (0b00, 2, ColorScheme::BlackAndWhite),
+            (0b010, 3, ColorScheme::Grayscale2Bit),
+            (0b011, 3, ColorScheme::Predefined2Bit),
+            (0b100, 3, ColorScheme::Rgb6Bit),
+            (0b101, 3, ColorScheme::Websafe),
+            (0b1100, 4, ColorScheme::Rgb6BitPalette),
+            (0b1101, 4, ColorScheme::WebsafePalette),
+            (0b1110, 4, ColorScheme::Rgb12Bit),
+            (0b1111, 4, ColorScheme::Rgb24Bit),
+        ];
+
+        for (code, n, expected) in cases {
+            let mut w = prefix(code, n);
+            w.push(0xFF, 8);
+            assert_eq!(decode_color_scheme(w), (expected, n as usize), "prefix {:b}", code);
+        }
+    }
+
+    /// Decodes a color configuration without default colors from the
+    /// written bits and returns it with the total number of bits consumed.
+    fn decode_color_configuration(mut w: BitWriter) -> (ColorConfig, usize) {
+        w.push(0b000, 3).push(0xFF, 8);
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        let config = parser.parse_color_configuration().unwrap();
+        let consumed = parser.bs.byte_position() * 8 + parser.bs.bit_position() as usize;
+        (config, consumed)
+    }
+
+    #[test]
+    fn test_color_scheme_rgb6_palette() {
+        // 1100, 5-bit count (0 => 1 color), one 6-bit color
+        let mut w = prefix(0b1100, 4);
+        w.push(0, 5).push(0b110000, 6);
+        let (config, consumed) = decode_color_configuration(w);
+        assert_eq!(config.scheme, ColorScheme::Rgb6BitPalette);
+        assert_eq!(config.palette, vec![Color::new(255, 0, 0)]);
+        assert_eq!(consumed, 15 + 3);
+    }
+
+    #[test]
+    fn test_color_scheme_websafe_palette() {
+        // 1101, 7-bit count (1 => 2 colors), two 8-bit indices
+        let mut w = prefix(0b1101, 4);
+        w.push(1, 7).push(5, 8).push(17, 8);
+        let (config, consumed) = decode_color_configuration(w);
+        assert_eq!(config.scheme, ColorScheme::WebsafePalette);
+        assert_eq!(config.palette.len(), 2);
+        assert_eq!(consumed, 27 + 3);
+    }
+
+    #[test]
+    fn test_rgb6_palette_max_size() {
+        // Count field 31 => 32 colors, then a line color at the last index
+        let mut w = prefix(0b1100, 4);
+        w.push(31, 5);
+        for i in 0..32 {
+            w.push(i, 6);
+        }
+        w.push(1, 1).push(31, 5).push(0, 1).push(0, 1);
+        let (config, consumed) = decode_color_configuration(w);
+        assert_eq!(config.palette.len(), 32);
+        assert_eq!(config.default_line_color, Some(rgb6_color(31)));
+        assert_eq!(consumed, 4 + 5 + 32 * 6 + 1 + 5 + 2);
+    }
+
+    #[test]
+    fn test_websafe_palette_max_size() {
+        // Count field 127 => 128 colors, then a line color at the last index
+        let mut w = prefix(0b1101, 4);
+        w.push(127, 7);
+        for i in 0..128 {
+            w.push(255 - i, 8);
+        }
+        w.push(1, 1).push(127, 7).push(0, 1).push(0, 1);
+        let (config, consumed) = decode_color_configuration(w);
+        assert_eq!(config.palette.len(), 128);
+        assert_eq!(config.default_line_color, Some(websafe_color(128)));
+        assert_eq!(consumed, 4 + 7 + 128 * 8 + 1 + 7 + 2);
+    }
+
+    #[test]
+    fn test_palette_index_past_end() {
+        // Three colors take a 2-bit index, so index 3 is out of range
+        let mut w = prefix(0b1100, 4);
+        w.push(2, 5).push(0, 6).push(1, 6).push(2, 6).push(1, 1).push(3, 2);
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let result = WvgParser::new(&mut bs).parse_color_configuration();
+        assert!(matches!(
+            result,
+            Err(WvgError::InvalidColorIndex {
+                index: 3,
+                palette_len: 3
+            })
+        ));
+    }
+
+    fn palette_parser_color(index: u32) -> WvgResult<Color> {
+        let data = prefix(index, 2).into_bytes();
+        let mut bs = BitStream::new(&data);
+        let palette = vec![Color::WHITE, Color::new(255, 0, 0), Color::BLACK];
+        let mut parser = WvgParser::new(&mut bs).with_palette(palette);
+        parser.parse_draw_color(ColorScheme::Rgb6BitPalette)
+    }
+
+    #[test]
+    fn test_injected_palette_color_lookup() {
+        assert_eq!(palette_parser_color(1).unwrap(), Color::new(255, 0, 0));
+        assert_eq!(palette_parser_color(2).unwrap(), Color::BLACK);
+        assert!(matches!(
+            palette_parser_color(3),
+            Err(WvgError::InvalidColorIndex {
+                index: 3,
+                palette_len: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_palette_index_bits() {
+        assert_eq!(palette_index_bits(1), 0);
+        assert_eq!(palette_index_bits(2), 1);
+        assert_eq!(palette_index_bits(3), 2);
+        assert_eq!(palette_index_bits(32), 5);
+        assert_eq!(palette_index_bits(128), 7);
+    }
+
+    #[test]
+    fn test_scan_features_supported() {
+        let masks = [false, true, true, false, false, true, false, false];
+        let data = header(masks).into_bytes();
+        let report = WvgParser::scan_features(&data).unwrap();
+
+        assert_eq!(
+            report.element_types,
+            vec![ElementType::Polyline, ElementType::CircularPolyline, ElementType::Reuse]
+        );
+        assert!(!report.compact_coordinates);
+        assert!(report.is_supported());
+    }
+
+    #[test]
+    fn test_scan_features_unsupported() {
+        let masks = [false, true, false, true, false, false, false, true];
+        let data = header(masks).into_bytes();
+        let report = WvgParser::scan_features(&data).unwrap();
+
+        assert_eq!(report.animation_mode, Some(AnimationMode::Simple));
+        assert_eq!(
+            report.unsupported_features(),
+            vec![UnsupportedFeature::BezierPolyline, UnsupportedFeature::SimpleAnimation]
+        );
+    }
+
+    const CIRCULAR_ONLY: [bool; 8] = [false, false, true, false, false, false, false, false];
+
+    /// Builds a document with one circular polyline from (10, 10) whose
+    /// second point is written as the offset (2, -1).
+    fn relative_second_point_document() -> Vec<u8> {
+        let mut w = header(CIRCULAR_ONLY);
+        w.push(0, 1).push(1, 7);
+        // Offset bits, no curve hint, no extra points, point (10, 10)
+        w.push(0b00, 2).push(0, 1).push(0, 4).push(10, 7).push(10, 5);
+        // Straight segment to offset (2, -1) in 3-bit level 1 offsets
+        w.push(0, 4).push(2, 3).push(0b111, 3);
+        w.into_bytes()
+    }
+
+    #[test]
+    fn test_relative_second_circular_point() {
+        let options = ParseOptions::new().with_relative_second_point(true);
+        let doc = parse_with(&relative_second_point_document(), options).unwrap();
+
+        match &doc.elements[0].data {
+            ElementData::CircularPolyline(cp) => {
+                assert_eq!(cp.points.len(), 2);
+                assert!(cp.points[0].is_absolute);
+                assert_eq!((cp.points[0].point.x, cp.points[0].point.y), (10, 10));
+                assert!(!cp.points[1].is_absolute);
+                assert_eq!((cp.points[1].point.x, cp.points[1].point.y), (2, -1));
+            }
+            other => panic!("expected circular polyline, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_supported_features_match_parser() {
+        for index in 0..13 {
+            let mut masks = [false; 13];
+            masks[index] = true;
+            let mut w = header_with_masks(&masks);
+            w.push(0, 1).push(1, 7).push(0, 32);
+
+            let element_type = ElementType::from_mask_index(index).unwrap();
+            let listed = supported_features()
+                .iter()
+                .any(|f| f.parser && f.kind == FeatureKind::Element(element_type));
+            let result = parse_with(&w.into_bytes(), ParseOptions::default());
+            assert_eq!(
+                !matches!(result, Err(WvgError::UnsupportedFeature(_))),
+                listed,
+                "{:?}",
+                element_type
+            );
+        }
+    }
+
+    #[test]
+    fn test_scan_features_character_size() {
+        let report = WvgParser::scan_features(&[0x00]).unwrap();
+        assert_eq!(report.unsupported_features(), vec![UnsupportedFeature::CharacterSizeWvg]);
+    }
+
+    #[test]
+    fn test_parse_character_size_header() {
+        // Type 0, version 2, no extended info, black and white, default line
+        // color black, no fill or background color, then 0b1011
+        let data = [0b0001_0000, 0b1100_1011];
+        let mut bs = BitStream::new(&data);
+        let header = match WvgParser::new(&mut bs).parse() {
+            Err(WvgError::CharacterSizeWvg(header)) => header,
+            other => panic!("expected a character size header, got {:?}", other),
+        };
+
+        assert_eq!(header.general_info.version, 2);
+        assert_eq!(header.color_config.scheme, ColorScheme::BlackAndWhite);
+        assert_eq!(header.color_config.default_line_color, Some(Color::BLACK));
+        assert_eq!(header.color_config.default_fill_color, None);
+        assert_eq!(header.remaining_bit_len, 4);
+        assert_eq!(header.remaining_bits, vec![0b1011_0000]);
+
+        let mut bs = BitStream::new(&data);
+        let config = WvgParser::new(&mut bs).parse_color_config_only().unwrap();
+        assert_eq!(config, header.color_config);
+    }
+
+    #[test]
+    fn test_channel_expansion_is_bit_replication() {
+        for v in 0..4u32 {
+            let replicated = (v << 6) | (v << 4) | (v << 2) | v;
+            assert_eq!(u32::from(expand_2bit(v)), replicated);
+        }
+        for v in 0..16u32 {
+            assert_eq!(u32::from(expand_4bit(v)), (v << 4) | v);
+        }
+    }
+
+    fn decode_color(scheme: ColorScheme, value: u32, n: u8) -> Color {
+        let data = prefix(value, n).into_bytes();
+        let mut bs = BitStream::new(&data);
+        WvgParser::new(&mut bs).parse_draw_color(scheme).unwrap()
+    }
+
+    #[test]
+    fn test_draw_color_grayscale() {
+        let grays: Vec<u8> = (0..4)
+            .map(|v| decode_color(ColorScheme::Grayscale2Bit, v, 2).r)
+            .collect();
+        assert_eq!(grays, vec![0, 85, 170, 255]);
+    }
+
+    #[test]
+    fn test_draw_color_rgb6() {
+        assert_eq!(decode_color(ColorScheme::Rgb6Bit, 0b100111, 6), Color::new(170, 85, 255));
+        assert_eq!(decode_color(ColorScheme::Rgb6Bit, 0b111111, 6), Color::WHITE);
+        assert_eq!(decode_color(ColorScheme::Rgb6Bit, 0b000000, 6), Color::BLACK);
+    }
+
+    #[test]
+    fn test_draw_color_rgb12() {
+        assert_eq!(decode_color(ColorScheme::Rgb12Bit, 0xF80, 12), Color::new(255, 136, 0));
+        assert_eq!(decode_color(ColorScheme::Rgb12Bit, 0x1A5, 12), Color::new(17, 170, 85));
+    }
+
+    #[test]
+    fn test_draw_color_fixed_schemes() {
+        assert_eq!(decode_color(ColorScheme::BlackAndWhite, 1, 1), Color::BLACK);
+        assert_eq!(decode_color(ColorScheme::BlackAndWhite, 0, 1), Color::WHITE);
+        assert_eq!(decode_color(ColorScheme::Predefined2Bit, 2, 2), Color::new(0, 255, 0));
+        assert_eq!(decode_color(ColorScheme::Rgb24Bit, 0x123456, 24), Color::new(0x12, 0x34, 0x56));
+    }
+
+    fn timestamp_bits(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> Vec<u8> {
+        let mut w = BitWriter::new();
+        w.push(1, 1).push(year as u32, 13).push(month, 4).push(day, 5);
+        w.push(hour, 5).push(minute, 6).push(30, 6);
+        w.into_bytes()
+    }
+
+    fn parse_timestamp_with(data: &[u8], options: ParseOptions) -> WvgResult<Option<Timestamp>> {
+        let mut bs = BitStream::new(data);
+        WvgParser::with_options(&mut bs, options).parse_timestamp()
+    }
+
+    #[test]
+    fn test_timestamp_valid() {
+        let data = timestamp_bits(2024, 2, 29, 13, 5);
+        let ts = parse_timestamp_with(&data, ParseOptions::new().with_strict(true))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ts.to_rfc3339().as_deref(), Some("2024-02-29T13:05:30Z"));
+    }
+
+    #[test]
+    fn test_timestamp_invalid_strict() {
+        let data = timestamp_bits(2023, 15, 0, 13, 5);
+        let result = parse_timestamp_with(&data, ParseOptions::new().with_strict(true));
+        assert!(matches!(result, Err(WvgError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_timestamp_invalid_lenient() {
+        let data = timestamp_bits(2023, 2, 31, 25, 61);
+        let ts = parse_timestamp_with(&data, ParseOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(ts.to_rfc3339().as_deref(), Some("2023-02-28T23:59:30Z"));
+    }
+
+    #[test]
+    fn test_timestamp_rfc3339_rejects_invalid() {
+        let ts = Timestamp {
+            year: 2023,
+            month: 2,
+            day: 29,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+        assert_eq!(ts.to_rfc3339(), None);
+        assert_eq!(Timestamp { year: -5, day: 28, ..ts }.to_rfc3339(), None);
+    }
+
+    #[test]
+    fn test_timestamp_to_unix() {
+        let data = timestamp_bits(2024, 2, 29, 13, 5);
+        let ts = parse_timestamp_with(&data, ParseOptions::default()).unwrap().unwrap();
+        assert_eq!(ts.to_unix(), Some(1_709_211_930));
+
+        let epoch = Timestamp {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+        assert_eq!(epoch.to_unix(), Some(0));
+        assert_eq!(Timestamp { year: 1900, ..epoch }.to_unix(), Some(-2_208_988_800));
+        let before = Timestamp { year: 1969, month: 12, day: 31, hour: 23, minute: 59, second: 59 };
+        assert_eq!(before.to_unix(), Some(-1));
+        assert_eq!(Timestamp { year: 2023, month: 2, day: 29, ..epoch }.to_unix(), None);
+
+        // Negative years come from the 13-bit signed field
+        let data = timestamp_bits(-1, 3, 1, 0, 0);
+        let ts = parse_timestamp_with(&data, ParseOptions::default()).unwrap().unwrap();
+        assert_eq!(ts.year, -1);
+        assert_eq!(ts.to_unix(), Some(-62_193_657_600 + 30));
+    }
+
+    const POLYLINE_AND_REUSE: [bool; 8] = [false, true, false, false, false, true, false, false];
+
+    /// Element masks enabling only special shapes.
+    fn special_shape_masks() -> [bool; 13] {
+        let mut masks = [false; 13];
+        masks[9] = true;
+        masks
+    }
+
+    #[test]
+    fn test_parse_star() {
+        let mut w = header_with_masks(&special_shape_masks());
+        w.push(0, 1).push(2, 7);
+        // Star: offset bits, kind 0, 5 points, center (20, 10), radii 8 and
+        // 3, no rotation
+        w.push(0b00, 2).push(0, 1).push(5, 4).push(20, 7).push(10, 5);
+        w.push(8, 7).push(3, 7).push(0, 1);
+        // Star rotated by 2 angle steps with equal radii
+        w.push(0b00, 2).push(0, 1).push(4, 4).push(20, 7).push(10, 5);
+        w.push(6, 7).push(6, 7).push(1, 1).push(2, 3);
+
+        let doc = parse_with(&w.into_bytes(), ParseOptions::default()).unwrap();
+        let stars: Vec<&StarShape> = doc
+            .elements
+            .iter()
+            .map(|element| match &element.data {
+                ElementData::SpecialShape(SpecialShapeElement {
+                    shape: SpecialShape::Star(star),
+                    ..
+                }) => star,
+                other => panic!("expected star, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(stars[0].num_points, 5);
+        assert_eq!((stars[0].center.x, stars[0].center.y), (20, 10));
+        assert_eq!((stars[0].outer_radius, stars[0].inner_radius), (8, 3));
+        assert_eq!(stars[0].rotation, None);
+        assert_eq!(stars[1].num_points, 4);
+        assert_eq!(stars[1].rotation, Some(2));
+        assert_eq!(stars[1].outer_radius, stars[1].inner_radius);
+    }
+
+    #[test]
+    fn test_parse_grid_unsupported() {
+        let mut w = header_with_masks(&special_shape_masks());
+        w.push(0, 1).push(1, 7);
+        w.push(0b00, 2).push(1, 1).push(0, 32);
+
+        let result = parse_with(&w.into_bytes(), ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::UnsupportedFeature(UnsupportedFeature::SpecialShape))
+        ));
+    }
+
+    /// Builds a document with a single-point polyline followed by a reuse
+    /// element referencing `index`.
+    fn reuse_document(index: u32) -> Vec<u8> {
+        let mut w = header(POLYLINE_AND_REUSE);
+        w.push(0, 1).push(2, 7);
+        // Polyline: type 0, offset bits, no extra points, point (3, 4)
+        w.push(0, 1).push(0b00, 2).push(0, 4).push(3, 7).push(4, 5);
+        // Reuse: type 1, 3-bit index, empty transform, no array, no override
+        w.push(1, 1).push(index, 3).push(0b000, 3).push(0, 1).push(0, 1);
+        w.into_bytes()
+    }
+
+    #[test]
+    fn test_minimal_reuse_element() {
+        let mut w = header(POLYLINE_AND_REUSE);
+        w.push(0, 1).push(3, 7);
+        // Polyline: type 0, offset bits, no extra points, point (3, 4)
+        w.push(0, 1).push(0b00, 2).push(0, 4).push(3, 7).push(4, 5);
+        // Bare reuse: type 1, index 0, all transform flags unset, no array,
+        // no override
+        w.push(1, 1).push(0, 3).push(0b000, 3).push(0, 1).push(0, 1);
+        // Polyline at (5, 6) to check that the reuse consumed exactly its bits
+        w.push(0, 1).push(0b00, 2).push(0, 4).push(5, 7).push(6, 5);
+
+        let doc = parse_with(&w.into_bytes(), ParseOptions::default()).unwrap();
+        match &doc.elements[1].data {
+            ElementData::Reuse(reuse) => {
+                let t = &reuse.transform;
+                assert!(t.translate_x.is_none() && t.translate_y.is_none());
+                assert!(t.angle.is_none() && t.scale_x.is_none() && t.scale_y.is_none());
+                assert!(reuse.array_params.is_none());
+                assert!(reuse.override_attributes.is_none());
+            }
+            other => panic!("expected reuse element, got {:?}", other),
+        }
+        match &doc.elements[2].data {
+            ElementData::Polyline(pl) => assert_eq!((pl.points[0].x, pl.points[0].y), (5, 6)),
+            other => panic!("expected polyline element, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reuse_index_in_bounds() {
+        let doc = parse_with(&reuse_document(0), ParseOptions::default()).unwrap();
+        match &doc.elements[1].data {
+            ElementData::Reuse(reuse) => assert_eq!(reuse.element_index, 0),
+            other => panic!("expected reuse element, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reuse_index_out_of_bounds() {
+        let result = parse_with(&reuse_document(5), ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::InvalidReuse { index: 5, position: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_reuse_self_reference() {
+        let result = parse_with(&reuse_document(1), ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::InvalidReuse { index: 1, position: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_circular_polyline_requires_curve_offset_bits() {
+        // Header without the circular polyline mask, so no curve offset width
+        let mut w = header(POLYLINE_AND_REUSE);
+        w.push(0, 32);
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        parser.bs.read_bit().unwrap();
+        parser.parse_standard_wvg_header().unwrap();
+        assert_eq!(parser.generic_params.curve_offset_in_bits, None);
+
+        let result = parser.parse_circular_polyline_element();
+        assert!(matches!(result, Err(WvgError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_polyline_offsets_are_all_relative() {
+        let mut w = header(POLYLINE_AND_REUSE);
+        // No offset bit use, two offsets, start (10, 5), offsets (3, 0) and
+        // (-4, 3) at the 3-bit level
+        w.push(0b00, 2).push(2, 4).push(10, 7).push(5, 5);
+        w.push(3, 3).push(0, 3).push(0b100, 3).push(3, 3);
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        parser.bs.read_bit().unwrap();
+        parser.parse_standard_wvg_header().unwrap();
+
+        let ElementData::Polyline(pl) = parser.parse_polyline_element().unwrap() else {
+            panic!("expected a polyline");
+        };
+        let points: Vec<_> = pl.points.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(points, vec![(10, 5), (13, 5), (9, 8)]);
+    }
+
+    #[test]
+    fn test_parse_embedded_palette() {
+        // Standard WVG, version 0, no extended info
+        let mut w = BitWriter::new();
+        w.push(1, 1).push(0, 4).push(0, 1);
+        // 6-bit palette of red and blue, no default colors
+        w.push(0b1100, 4).push(1, 5).push(0b110000, 6).push(0b000011, 6).push(0b000, 3);
+        // No element or attribute masks, default generic parameters
+        w.push(0, 9).push(0, 4).push(0b000, 3);
+        // Flat coordinates as in `header`
+        w.push(0, 1).push(128, 16).push(0, 1);
+        w.push(7, 4).push(5, 4).push(1, 1).push(7, 4).push(4, 4);
+        w.push(3, 4).push(3, 4).push(5, 4).push(5, 4);
+        // No elements
+        w.push(0, 8);
+        let data = w.into_bytes();
+
+        let mut bs = BitStream::new(&data);
+        let doc = WvgParser::new(&mut bs).parse().unwrap();
+        let config = &doc.header.color_config;
+        assert_eq!(config.scheme, ColorScheme::Rgb6BitPalette);
+        assert_eq!(config.palette, vec![Color::new(255, 0, 0), Color::new(0, 0, 255)]);
+        assert!(doc.elements.is_empty());
+    }
+
+    #[test]
+    fn test_embedded_palette_replaces_injected() {
+        // 1100, one embedded color (red), default line color index 0 (0 bits),
+        // no default fill or background color
+        let mut w = prefix(0b1100, 4);
+        w.push(0, 5).push(0b110000, 6).push(1, 1).push(0, 1).push(0, 1);
+        let data = w.into_bytes();
+
+        let mut bs = BitStream::new(&data);
+        let injected = vec![Color::WHITE, Color::new(0, 255, 0), Color::BLACK];
+        let mut parser = WvgParser::new(&mut bs).with_palette(injected);
+        let config = parser.parse_color_configuration().unwrap();
+
+        assert_eq!(config.palette, vec![Color::new(255, 0, 0)]);
+        assert_eq!(config.default_line_color, Some(Color::new(255, 0, 0)));
+    }
+
+    #[test]
+    fn test_palette_only_kept_for_palette_schemes() {
+        // Black and white, no default colors
+        let data = prefix(0b00_000, 5).into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs).with_palette(vec![Color::WHITE]);
+        let config = parser.parse_color_configuration().unwrap();
+        assert!(config.palette.is_empty());
+    }
+
+    #[test]
+    fn test_decode_ucs2_bmp() {
+        assert_eq!(decode_ucs2(&[0x0057, 0x00e9], false), "W\u{e9}");
+        assert_eq!(decode_ucs2(&[0x0057, 0x00e9], true), "W\u{e9}");
+    }
+
+    #[test]
+    fn test_decode_ucs2_surrogate_pair() {
+        let units = [0xd83d, 0xde00];
+        assert_eq!(decode_ucs2(&units, false), "\u{1f600}");
+        assert_eq!(decode_ucs2(&units, true), "\u{fffd}\u{fffd}");
+        assert_eq!(decode_ucs2(&[0xd83d, 0x0041], false), "\u{fffd}A");
+    }
+
+    #[test]
+    fn test_parse_ucs2_title() {
+        let mut w = prefix(1, 1);
+        w.push(3, 8).push(0x0041, 16).push(0xd83d, 16).push(0xde00, 16);
+        let data = w.into_bytes();
+
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        let title = parser.parse_optional_string(TextCodeMode::Ucs2).unwrap();
+        assert_eq!(title.as_deref(), Some("A\u{1f600}"));
+
+        let mut bs = BitStream::new(&data);
+        let options = ParseOptions::new().with_strict_ucs2(true);
+        let mut parser = WvgParser::with_options(&mut bs, options);
+        let title = parser.parse_optional_string(TextCodeMode::Ucs2).unwrap();
+        assert_eq!(title.as_deref(), Some("A\u{fffd}\u{fffd}"));
+    }
+
+    #[test]
+    fn test_parse_ucs2_title_max_length() {
+        let mut w = prefix(1, 1);
+        w.push(MAX_STRING_LENGTH as u32, 8);
+        for _ in 0..MAX_STRING_LENGTH {
+            w.push(0x0041, 16);
+        }
+        w.push(1, 1);
+        let data = w.into_bytes();
+
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        let title = parser.parse_optional_string(TextCodeMode::Ucs2).unwrap();
+        assert_eq!(title, Some("A".repeat(MAX_STRING_LENGTH)));
+        assert_eq!(parser.bs.read_bit().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_element_context_wraps_element_errors() {
+        let options = ParseOptions::new().with_element_context(true);
+        let result = parse_with(&reuse_document(5), options);
+        match result {
+            Err(WvgError::ElementError {
+                index,
+                raw_type,
+                source,
+            }) => {
+                assert_eq!(index, 1);
+                assert_eq!(raw_type, Some(1));
+                assert!(matches!(
+                    *source,
+                    WvgError::InvalidReuse { index: 5, position: 1 }
+                ));
+            }
+            other => panic!("expected element error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_element_context_not_applied_after_elements() {
+        // The unbalanced group is only detected once all elements are read
+        let options = ParseOptions::new()
+            .with_strict(true)
+            .with_element_context(true);
+        let result = parse_with(&group_document(&[true]), options);
+        assert!(matches!(result, Err(WvgError::ParseError(_))));
+    }
+
+    /// Polyline, reuse and group enabled, so element types use 2 bits.
+    const THREE_TYPES: [bool; 8] = [false, true, false, false, false, true, true, false];
+
+    #[test]
+    fn test_element_mask_without_extension() {
+        let data = header(THREE_TYPES).into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        parser.bs.read_bit().unwrap();
+        parser.parse_general_info().unwrap();
+        parser.parse_color_configuration().unwrap();
+        parser.parse_element_mask().unwrap();
+        assert_eq!(parser.element_masks.len(), 8);
+
+        let report = WvgParser::scan_features(&data).unwrap();
+        assert!(!report.element_types.contains(&ElementType::Polygon));
+    }
+
+    #[test]
+    fn test_element_mask_with_extension() {
+        let mut masks = [false; 13];
+        masks[1] = true;
+        masks[8] = true;
+        let data = header_with_masks(&masks).into_bytes();
+
+        let report = WvgParser::scan_features(&data).unwrap();
+        assert_eq!(report.element_types, vec![ElementType::Polyline, ElementType::Polygon]);
+    }
+
+    #[test]
+    fn test_element_type_beyond_base_masks_rejected() {
+        // The fourth type index would be an extended type, but the extension
+        // bit is unset
+        let mut w = header(THREE_TYPES);
+        w.push(0, 1).push(1, 7);
+        w.push(3, 2);
+        let data = w.into_bytes();
+        let result = parse_with(&data, ParseOptions::default());
+        assert!(matches!(result, Err(WvgError::InvalidElementType(3))));
+
+        let options = ParseOptions::new().with_element_context(true);
+        let result = parse_with(&data, options);
+        assert!(matches!(
+            result,
+            Err(WvgError::ElementError {
+                index: 0,
+                raw_type: Some(3),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_extended_element_type_selected() {
+        let mut masks = [false; 13];
+        masks[1] = true;
+        masks[8] = true;
+        let mut w = header_with_masks(&masks);
+        w.push(0, 1).push(1, 7);
+        w.push(1, 1);
+        let result = parse_with(&w.into_bytes(), ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::UnsupportedFeature(UnsupportedFeature::Polygon))
+        ));
+    }
+
+    #[test]
+    fn test_extended_element_rejected() {
+        let mut masks = [false; 13];
+        masks[1] = true;
+        masks[12] = true;
+        let mut w = header_with_masks(&masks);
+        // An extended element followed by a polyline; parsing must stop at
+        // the extended element rather than read the polyline misaligned
+        w.push(0, 1).push(2, 7);
+        w.push(1, 1).push(0xFF, 8);
+        w.push(0, 1);
+        let result = parse_with(&w.into_bytes(), ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::UnsupportedFeature(UnsupportedFeature::ExtendedElement))
+        ));
+    }
+
+    const GROUP_ONLY: [bool; 8] = [false, false, false, false, false, false, true, false];
+
+    /// Builds a document consisting only of group elements, where `true`
+    /// denotes a group start and `false` a group end.
+    fn group_document(groups: &[bool]) -> Vec<u8> {
+        let mut w = header(GROUP_ONLY);
+        w.push(0, 1).push(groups.len() as u32, 7);
+        for &start in groups {
+            if start {
+                // Group start, no transform, displayed
+                w.push(0, 1).push(0, 1).push(1, 1);
+            } else {
+                w.push(1, 1);
+            }
+        }
+        w.into_bytes()
+    }
+
+    #[test]
+    fn test_group_start_bit_order() {
+        let mut w = header(GROUP_ONLY);
+        w.push(0, 1).push(4, 7);
+        // Group start with a transform translating x by 5, hidden
+        w.push(0, 1).push(1, 1);
+        w.push(1, 1).push(5, 7).push(0, 1).push(0, 1);
+        w.push(0, 1);
+        // Group end
+        w.push(1, 1);
+        // Group start without a transform, displayed, then its end
+        w.push(0, 1).push(0, 1).push(1, 1);
+        w.push(1, 1);
+
+        let options = ParseOptions::new().with_strict(true);
+        let doc = parse_with(&w.into_bytes(), options).unwrap();
+        assert_eq!(doc.elements.len(), 4);
+
+        match &doc.elements[0].data {
+            ElementData::GroupStart(gs) => {
+                let t = gs.transform.as_ref().unwrap();
+                assert_eq!(t.translate_x, Some(5));
+                assert!(t.translate_y.is_none() && t.angle.is_none());
+                assert!(!gs.display);
+            }
+            other => panic!("expected group start, got {:?}", other),
+        }
+        assert!(matches!(doc.elements[1].data, ElementData::GroupEnd));
+        match &doc.elements[2].data {
+            ElementData::GroupStart(gs) => {
+                assert!(gs.transform.is_none());
+                assert!(gs.display);
+            }
+            other => panic!("expected group start, got {:?}", other),
+        }
+        assert!(matches!(doc.elements[3].data, ElementData::GroupEnd));
+    }
+
+    #[test]
+    fn test_attribute_presence_bit_requires_masks() {
+        // Offset bit use (1, 0), then the next bit of the element
+        let mut w = BitWriter::new();
+        w.push(0b10, 2).push(1, 1);
+        let data = w.into_bytes();
+
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        let attrs = parser.parse_basic_element_header().unwrap();
+        assert!(attrs.line_type.is_none() && attrs.line_width.is_none() && attrs.fill.is_none());
+        assert!(parser.offset_x_use && !parser.offset_y_use);
+        assert_eq!(parser.bs.bit_position(), 2);
+
+        // With a mask set the third bit is the presence bit
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        parser.attribute_masks.line_width = true;
+        let attrs = parser.parse_basic_element_header().unwrap();
+        assert_eq!(attrs.line_width, Some(LineWidth::None));
+        assert_eq!(parser.bs.bit_position(), 5);
+    }
+
+    #[test]
+    fn test_zero_line_width_skips_line_color() {
+        // Width 0, then fill with a specified color, then a marker bit; the
+        // line color flag is not present
+        let mut w = BitWriter::new();
+        w.push(0b00, 2).push(1, 1).push(1, 1).push(1, 1);
+        let data = w.into_bytes();
+        let mut bs = BitStream::new(&data);
+        let mut parser = WvgParser::new(&mut bs);
+        parser.attribute_masks = AttributeMasks {
+            line_width: true,
+            line_color: true,
+            fill: true,
+            ..Default::default()
+        };
+
+        let attrs = parser.parse_attributes_set().unwrap();
+        assert_eq!(attrs.line_width, Some(LineWidth::None));
+        assert_eq!(attrs.line_color, None);
+        assert_eq!(attrs.fill, Some(true));
+        assert!(attrs.fill_color.is_some());
+        assert_eq!(parser.bs.bit_position(), 4);
+        assert_eq!(parser.bs.read_bit().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_element_count_exceeds_data() {
+        let mut w = header(POLYLINE_AND_REUSE);
+        w.push(1, 1).push(32000, 15);
+        let mut data = w.into_bytes();
+        data.resize(100, 0);
+
+        let result = parse_with(&data, ParseOptions::default());
+        assert!(matches!(
+            result,
+            Err(WvgError::LimitExceeded { what: "element count", value: 32000, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_empty_input() {
+        let result = parse_with(&[], ParseOptions::default());
+        assert!(matches!(result, Err(WvgError::EndOfStream)));
+
+        // A stream cut off after the type bit fails the same way
+        let result = parse_with(&[0x80], ParseOptions::default());
+        assert!(matches!(result, Err(WvgError::EndOfStream)));
+    }
+
+    #[test]
+    fn test_empty_input_header_only_entry_points() {
+        assert!(matches!(WvgParser::scan_features(&[]), Err(WvgError::EndOfStream)));
+
+        let mut bs = BitStream::new(&[]);
+        let result = WvgParser::new(&mut bs).parse_color_config_only();
+        assert!(matches!(result, Err(WvgError::EndOfStream)));
+    }
+
+    /// Reads a point with 4-bit signed coordinates using `options`.
+    fn signed_point(data: &[u8], options: ParseOptions) -> Point {
+        let mut bs = BitStream::new(data);
+        let mut parser = WvgParser::with_options(&mut bs, options);
+        parser.flat_params = Some(FlatCoordinateParams {
+            drawing_width: 16,
+            drawing_height: 16,
+            max_x_in_bits: 4,
+            max_y_in_bits: 4,
+            xy_all_positive: false,
+            trans_xy_in_bits: 4,
+            num_points_in_bits: 4,
+            offset_x_in_bits_level1: 3,
+            offset_y_in_bits_level1: 3,
+            offset_x_in_bits_level2: 4,
+            offset_y_in_bits_level2: 4,
+        });
+        parser.parse_point().unwrap()
+    }
+
+    #[test]
+    fn test_signed_point_twos_complement() {
+        let point = signed_point(&[0b1011_1110], ParseOptions::default());
+        assert_eq!((point.x, point.y), (-5, -2));
+    }
+
+    #[test]
+    fn test_signed_point_sign_magnitude() {
+        let options = ParseOptions::new().with_sign_magnitude(true);
+        let point = signed_point(&[0b1011_1110], options);
+        assert_eq!((point.x, point.y), (-3, -6));
+    }
+
+    fn parse_with(data: &[u8], options: ParseOptions) -> WvgResult<WvgDocument> {
+        let mut bs = BitStream::new(data);
+        WvgParser::with_options(&mut bs, options).parse()
+    }
+
+    #[test]
+    fn test_group_attributes_parsed() {
+        // Polyline and group elements, line width attribute mask only
+        let masks = [false, true, false, false, false, false, true, false];
+        let mut w = header_with_attribute_masks(&masks, 0b0100);
+        w.push(0, 1).push(3, 7);
+        // Group start: type 1, start, no transform, displayed, thick lines
+        w.push(1, 1).push(0, 1).push(0, 1).push(1, 1).push(1, 1).push(3, 2);
+        // Polyline: type 0, offset bits, no attributes, no extra points,
+        // point (3, 4)
+        w.push(0, 1).push(0b00, 2).push(0, 1).push(0, 4).push(3, 7).push(4, 5);
+        // Group end
+        w.push(1, 1).push(1, 1);
+
+        let doc = parse_with(&w.into_bytes(), ParseOptions::default()).unwrap();
+        match &doc.elements[0].data {
+            ElementData::GroupStart(gs) => {
+                assert!(gs.display);
+                assert_eq!(gs.attributes.line_width, Some(LineWidth::Thick));
+            }
+            other => panic!("expected group start, got {:?}", other),
+        }
+        assert!(matches!(doc.elements[2].data, ElementData::GroupEnd));
+        assert_eq!(doc.effective_attributes(1).line_width, Some(LineWidth::Thick));
+    }
+
+    #[test]
+    fn test_read_until_eof() {
+        // Declares a single element but contains four
+        let mut w = header(GROUP_ONLY);
+        w.push(0, 1).push(1, 7);
+        for _ in 0..2 {
+            w.push(0b001, 3).push(1, 1);
+        }
+        let data = w.into_bytes();
+
+        let declared = parse_with(&data, ParseOptions::default()).unwrap();
+        assert_eq!(declared.elements.len(), 1);
+
+        let options = ParseOptions::new().with_read_until_eof(true);
+        let until_eof = parse_with(&data, options).unwrap();
+        assert_eq!(until_eof.elements.len(), 4);
+    }
+
+    #[test]
+    fn test_balanced_groups() {
+        let data = group_document(&[true, true, false, false]);
+        let doc = parse_with(&data, ParseOptions::new().with_strict(true)).unwrap();
+        assert_eq!(doc.elements.len(), 4);
+    }
+
+    #[test]
+    fn test_missing_group_end() {
+        let data = group_document(&[true, true, false]);
+
+        let strict = parse_with(&data, ParseOptions::new().with_strict(true));
+        assert!(matches!(strict, Err(WvgError::ParseError(_))));
+
+        let lenient = parse_with(&data, ParseOptions::default()).unwrap();
+        assert_eq!(lenient.elements.len(), 3);
+    }
+
+    #[test]
+    fn test_extra_group_end() {
+        let data = group_document(&[true, false, false]);
+
+        let strict = parse_with(&data, ParseOptions::new().with_strict(true));
+        assert!(matches!(strict, Err(WvgError::ParseError(_))));
+
+        let lenient = parse_with(&data, ParseOptions::default()).unwrap();
+        assert_eq!(lenient.elements.len(), 3);
+    }
+}
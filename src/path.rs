@@ -0,0 +1,896 @@
+//! Path data converter implementation for WVG documents.
+//!
+//! This module provides a `Converter` that flattens a document into plain SVG
+//! path data strings, for rendering backends that only consume paths, and
+//! [`WvgDocument::primitives`] for consumers that want the resolved line and
+//! arc segments themselves.
+
+use std::cell::Cell;
+use std::fmt::Write;
+
+use crate::converter::{Converter, ConverterConfig};
+use crate::error::{WvgError, WvgResult};
+use crate::svg::{arc_flags, arc_geometry, MAX_RADIUS_FACTOR};
+use crate::types::*;
+use crate::log::{debug, trace};
+
+/// Maximum nesting of reuse elements that reference other reuse elements.
+pub(crate) const MAX_REUSE_DEPTH: usize = 16;
+
+/// Converter that produces one SVG path data (`d`) string per drawable element.
+///
+/// Reuse elements and group transforms are resolved, so every string uses
+/// absolute commands in drawing coordinates (multiplied by the configured
+/// output scale). Elements inside hidden groups are skipped, and array reuse
+/// elements yield a single string with one subpath per instance. Local
+/// envelopes are ignored, since path data cannot express clipping.
+///
+/// # Example
+///
+/// ```ignore
+/// use wvg::{BitStream, WvgParser, PathDataConverter, Converter};
+///
+/// let data = std::fs::read("input.wvg")?;
+/// let mut bs = BitStream::new(&data);
+/// let document = WvgParser::new(&mut bs).parse()?;
+///
+/// for d in PathDataConverter::new().convert(&document)? {
+///     println!("{}", d);
+/// }
+/// ```
+pub struct PathDataConverter {
+    /// Configuration options.
+    config: ConverterConfig,
+}
+
+impl PathDataConverter {
+    /// Creates a new path data converter with default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: ConverterConfig::default(),
+        }
+    }
+
+    /// Creates a new path data converter with the given configuration.
+    pub fn with_config(config: ConverterConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Default for PathDataConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for PathDataConverter {
+    type Output = Vec<String>;
+
+    fn convert(&self, document: &WvgDocument) -> WvgResult<Self::Output> {
+        let ctx = PathContext::new(document, self.config.max_array_instances)?;
+        let scale = self.config.output_scale;
+
+        let mut paths: Vec<String> = Vec::new();
+        ctx.walk(&document.elements, Affine::scale(scale, scale), None, 0, false, &mut paths)?;
+        debug!("Generated {} path data strings", paths.len());
+        Ok(paths)
+    }
+}
+
+/// Stroke settings of a primitive, taken from the effective attributes of
+/// the element it was drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Stroke {
+    /// Line color, falling back to the document default.
+    pub color: Option<Color>,
+    /// Line width.
+    pub width: Option<LineWidth>,
+    /// Line type.
+    pub line_type: Option<LineType>,
+}
+
+impl From<&ElementAttributes> for Stroke {
+    fn from(attrs: &ElementAttributes) -> Self {
+        Self {
+            color: attrs.line_color,
+            width: attrs.line_width,
+            line_type: attrs.line_type,
+        }
+    }
+}
+
+/// A single drawable segment in absolute drawing coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    /// A straight line.
+    Line {
+        /// Start point.
+        from: (f64, f64),
+        /// End point.
+        to: (f64, f64),
+        /// Effective stroke.
+        stroke: Stroke,
+    },
+    /// An elliptical arc, parameterised like an SVG `A` command.
+    Arc {
+        /// Start point.
+        from: (f64, f64),
+        /// End point.
+        to: (f64, f64),
+        /// Semi-axes of the ellipse.
+        radii: (f64, f64),
+        /// Rotation of the ellipse's x-axis in degrees.
+        rotation: f64,
+        /// Whether the arc spans more than 180 degrees.
+        large_arc: bool,
+        /// Whether the arc is drawn in the positive-angle direction.
+        sweep: bool,
+        /// Effective stroke.
+        stroke: Stroke,
+    },
+}
+
+impl WvgDocument {
+    /// Returns the line and arc segments this document draws, in drawing
+    /// coordinates.
+    ///
+    /// Groups and reuse elements are expanded with their transforms applied,
+    /// elements in hidden groups are skipped, and closed shapes end with a
+    /// line back to their start. Fails under the same conditions as
+    /// [`PathDataConverter`] with the default configuration, including the
+    /// default `max_array_instances` budget for reused content.
+    pub fn primitives(&self) -> WvgResult<impl Iterator<Item = Primitive>> {
+        let max_instances = ConverterConfig::default().max_array_instances;
+        let ctx = PathContext::new(self, max_instances)?;
+
+        let mut parts: Vec<Primitives> = Vec::new();
+        ctx.walk(&self.elements, Affine::IDENTITY, None, 0, false, &mut parts)?;
+        Ok(parts.into_iter().flat_map(|part| part.primitives))
+    }
+}
+
+/// A 2D affine transform mapping `(x, y)` to
+/// `(a * x + c * y + e, b * x + d * y + f)`, as in SVG's `matrix()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Affine {
+    pub(crate) a: f64,
+    pub(crate) b: f64,
+    pub(crate) c: f64,
+    pub(crate) d: f64,
+    pub(crate) e: f64,
+    pub(crate) f: f64,
+}
+
+impl Affine {
+    pub(crate) const IDENTITY: Self = Self {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub(crate) fn translate(x: f64, y: f64) -> Self {
+        Self {
+            e: x,
+            f: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub(crate) fn scale(x: f64, y: f64) -> Self {
+        Self {
+            a: x,
+            d: y,
+            ..Self::IDENTITY
+        }
+    }
+
+    fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Returns the transform applying `inner` first and then `self`.
+    pub(crate) fn then(&self, inner: &Self) -> Self {
+        Self {
+            a: self.a * inner.a + self.c * inner.b,
+            b: self.b * inner.a + self.d * inner.b,
+            c: self.a * inner.c + self.c * inner.d,
+            d: self.b * inner.c + self.d * inner.d,
+            e: self.a * inner.e + self.c * inner.f + self.e,
+            f: self.b * inner.e + self.d * inner.f + self.f,
+        }
+    }
+
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// Returns the semi-axes and rotation (in degrees) of the ellipse a unit
+    /// circle is mapped to.
+    ///
+    /// Decomposes the linear part as `rotate(phi) * scale(sx, sy) * rotate(theta)`;
+    /// the inner rotation leaves the circle unchanged.
+    fn ellipse_axes(&self) -> (f64, f64, f64) {
+        let e = (self.a + self.d) / 2.0;
+        let f = (self.a - self.d) / 2.0;
+        let g = (self.b + self.c) / 2.0;
+        let h = (self.b - self.c) / 2.0;
+
+        let q = (e * e + h * h).sqrt();
+        let r = (f * f + g * g).sqrt();
+        // A circle stays a circle under similarity transforms, so any
+        // rotation is valid
+        let phi = if r < 1e-12 {
+            0.0
+        } else {
+            (g.atan2(f) + h.atan2(e)) / 2.0
+        };
+
+        (q + r, (q - r).abs(), phi.to_degrees())
+    }
+
+    /// Returns whether the transform mirrors the geometry.
+    fn flips(&self) -> bool {
+        self.a * self.d - self.b * self.c < 0.0
+    }
+}
+
+/// Receiver for transformed path geometry.
+pub(crate) trait PathSink: Default {
+    /// Sets the attributes of the element about to be drawn.
+    fn begin(&mut self, _attrs: &ElementAttributes) {}
+
+    fn move_to(&mut self, x: f64, y: f64);
+
+    fn line_to(&mut self, x: f64, y: f64);
+
+    /// Draws an elliptical arc with the given semi-axes and rotation.
+    #[allow(clippy::too_many_arguments)]
+    fn arc_to(&mut self, rx: f64, ry: f64, rotation: f64, large_arc: u8, sweep: u8, x: f64, y: f64);
+
+    /// Closes the current subpath.
+    fn close(&mut self);
+
+    fn is_empty(&self) -> bool;
+
+    /// Appends the geometry collected in `other`.
+    fn append(&mut self, other: Self);
+}
+
+impl PathSink for String {
+    fn move_to(&mut self, x: f64, y: f64) {
+        if !self.is_empty() {
+            self.push(' ');
+        }
+        write!(self, "M {} {}", number(x), number(y)).unwrap();
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        write!(self, " L {} {}", number(x), number(y)).unwrap();
+    }
+
+    fn arc_to(&mut self, rx: f64, ry: f64, rotation: f64, large_arc: u8, sweep: u8, x: f64, y: f64) {
+        write!(
+            self,
+            " A {} {} {} {} {} {} {}",
+            number(rx),
+            number(ry),
+            number(rotation),
+            large_arc,
+            sweep,
+            number(x),
+            number(y)
+        )
+        .unwrap();
+    }
+
+    fn close(&mut self) {
+        self.push_str(" Z");
+    }
+
+    fn is_empty(&self) -> bool {
+        str::is_empty(self)
+    }
+
+    fn append(&mut self, other: Self) {
+        if !PathSink::is_empty(self) {
+            self.push(' ');
+        }
+        self.push_str(&other);
+    }
+}
+
+/// Collects segments as [`Primitive`] values.
+#[derive(Default)]
+struct Primitives {
+    /// Stroke of the element being drawn.
+    stroke: Stroke,
+    /// Start of the current subpath.
+    start: (f64, f64),
+    /// Current point.
+    current: (f64, f64),
+    primitives: Vec<Primitive>,
+}
+
+impl PathSink for Primitives {
+    fn begin(&mut self, attrs: &ElementAttributes) {
+        self.stroke = Stroke::from(attrs);
+    }
+
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.start = (x, y);
+        self.current = (x, y);
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.primitives.push(Primitive::Line {
+            from: self.current,
+            to: (x, y),
+            stroke: self.stroke,
+        });
+        self.current = (x, y);
+    }
+
+    fn arc_to(&mut self, rx: f64, ry: f64, rotation: f64, large_arc: u8, sweep: u8, x: f64, y: f64) {
+        self.primitives.push(Primitive::Arc {
+            from: self.current,
+            to: (x, y),
+            radii: (rx, ry),
+            rotation,
+            large_arc: large_arc != 0,
+            sweep: sweep != 0,
+            stroke: self.stroke,
+        });
+        self.current = (x, y);
+    }
+
+    fn close(&mut self) {
+        if self.current != self.start {
+            let (x, y) = self.start;
+            self.line_to(x, y);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.primitives.is_empty()
+    }
+
+    fn append(&mut self, other: Self) {
+        self.primitives.extend(other.primitives);
+    }
+}
+
+/// Internal context for path data generation.
+pub(crate) struct PathContext<'a> {
+    /// The source document.
+    document: &'a WvgDocument,
+    /// Degrees per rotation step.
+    angle_resolution: f64,
+    /// Scale change per scale step.
+    scale_resolution: f64,
+    /// Largest number of reuse instances that may be expanded in total.
+    max_instances: usize,
+    /// Number of reuse instances expanded so far.
+    instances: Cell<usize>,
+}
+
+impl<'a> PathContext<'a> {
+    pub(crate) fn new(document: &'a WvgDocument, max_instances: usize) -> WvgResult<Self> {
+        let gp = &document.header.codec_params.generic_params;
+        if gp.angle_resolution > 3 {
+            return Err(WvgError::InvalidAngleResolution(gp.angle_resolution));
+        }
+
+        Ok(Self {
+            document,
+            angle_resolution: gp.angle_degrees(),
+            scale_resolution: gp.scale_step(),
+            max_instances,
+            instances: Cell::new(0),
+        })
+    }
+
+    /// Returns the number of reuse instances expanded so far.
+    pub(crate) fn instance_count(&self) -> usize {
+        self.instances.get()
+    }
+
+    /// Records `count` more expanded reuse instances.
+    ///
+    /// Every instance of an array reuse counts, as does every reuse inside
+    /// reused content, so nested reuses add up multiplicatively. Top-level
+    /// single reuses are as cheap as the elements they reference and do not
+    /// count. Fails with `WvgError::LimitExceeded` once the total exceeds
+    /// the budget, before the instances are expanded.
+    pub(crate) fn add_instances(&self, count: usize) -> WvgResult<()> {
+        let total = self.instances.get().saturating_add(count);
+        if total > self.max_instances {
+            return Err(WvgError::LimitExceeded {
+                what: "reuse instance count",
+                value: total,
+                limit: self.max_instances,
+            });
+        }
+        self.instances.set(total);
+        Ok(())
+    }
+
+    /// Appends the geometry of the drawable elements in `elements`, one sink
+    /// per element, with group transforms applied on top of `base`.
+    ///
+    /// `overrides` are the attributes of enclosing reuse elements, which take
+    /// precedence over those of the elements themselves. With `single_group`
+    /// set, stops once the group opened by the first element is closed.
+    fn walk<S: PathSink>(
+        &self,
+        elements: &[WvgElement],
+        base: Affine,
+        overrides: Option<&ElementAttributes>,
+        depth: usize,
+        single_group: bool,
+        paths: &mut Vec<S>,
+    ) -> WvgResult<()> {
+        // Transform, hidden flag and inherited attributes of each open group
+        let mut stack = vec![(base, false, ElementAttributes::default())];
+
+        for element in elements {
+            let (transform, hidden, inherited) = stack.last().unwrap().clone();
+
+            match &element.data {
+                ElementData::GroupStart(gs) => {
+                    let transform = gs
+                        .transform
+                        .as_ref()
+                        .map_or(transform, |t| transform.then(&self.transform(t)));
+                    stack.push((transform, hidden || !gs.display, gs.attributes.or(&inherited)));
+                }
+                ElementData::GroupEnd => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                    }
+                    if single_group && stack.len() == 1 {
+                        break;
+                    }
+                }
+                _ if hidden => {}
+                _ => {
+                    let mut d = S::default();
+                    if let Some(attrs) = element.data.attributes() {
+                        let attrs = overrides.map_or_else(|| attrs.clone(), |o| o.or(attrs));
+                        let attrs = attrs.or(&inherited);
+                        d.begin(&self.document.header.color_config.resolve(&attrs));
+                    }
+                    self.append_element(&mut d, element, &transform, overrides, depth)?;
+                    if !d.is_empty() {
+                        paths.push(d);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends path data for a single drawable element.
+    pub(crate) fn append_element<S: PathSink>(
+        &self,
+        d: &mut S,
+        element: &WvgElement,
+        m: &Affine,
+        overrides: Option<&ElementAttributes>,
+        depth: usize,
+    ) -> WvgResult<()> {
+        trace!("Converting element: {}", element.id);
+
+        match &element.data {
+            ElementData::Polyline(pl) => {
+                let points: Vec<(i32, i32)> = pl.points.iter().map(|p| (p.x, p.y)).collect();
+                append_lines(d, m, &points, false);
+            }
+            ElementData::CircularPolyline(cp) => self.append_circular_polyline(d, m, cp)?,
+            ElementData::SimpleShape(ss) => {
+                let m = match ss.rotation {
+                    Some(rotation) => {
+                        let (cx, cy) = ss.center();
+                        m.then(&Affine::translate(cx, cy))
+                            .then(&Affine::rotate(f64::from(rotation) * self.angle_resolution))
+                            .then(&Affine::translate(-cx, -cy))
+                    }
+                    None => *m,
+                };
+                append_simple_shape(d, &m, ss);
+            }
+            ElementData::SpecialShape(ss) => match &ss.shape {
+                SpecialShape::Grid(grid) => append_grid(d, m, grid, self.max_instances)?,
+                SpecialShape::Star(star) => {
+                    check_star_vertices(star, self.max_instances)?;
+                    append_polygon(d, m, &star.vertices(self.angle_resolution))
+                }
+            },
+            ElementData::Reuse(reuse) => self.append_reuse(d, m, reuse, overrides, depth)?,
+            ElementData::GroupStart(_) | ElementData::GroupEnd | ElementData::LocalEnvelope(_) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Appends a circular polyline as line and arc segments.
+    fn append_circular_polyline<S: PathSink>(
+        &self,
+        d: &mut S,
+        m: &Affine,
+        cp: &CircularPolylineElement,
+    ) -> WvgResult<()> {
+        if cp.points.len() < 2 {
+            return Ok(());
+        }
+
+        let mut points = Vec::with_capacity(cp.points.len());
+        let mut current = Point::new(0, 0);
+        for pt in &cp.points {
+            current = if pt.is_absolute {
+                pt.point
+            } else {
+                current
+                    .checked_offset(pt.point.x, pt.point.y)
+                    .ok_or(WvgError::CoordinateOverflow)?
+            };
+            points.push((current.x, current.y));
+        }
+
+        let (width, height) = self.document.header.codec_params.coord_params.dimensions();
+        let max_radius = MAX_RADIUS_FACTOR * f64::from(width.max(height).max(1));
+        let curve_offset_in_bits =
+            self.document.header.codec_params.generic_params.curve_offset_in_bits;
+
+        move_to(d, m, f64::from(points[0].0), f64::from(points[0].1));
+        for (pt, pair) in cp.points.iter().skip(1).zip(points.windows(2)) {
+            let (x1, y1) = (f64::from(pair[0].0), f64::from(pair[0].1));
+            let (x2, y2) = (f64::from(pair[1].0), f64::from(pair[1].1));
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let chord_len = (dx * dx + dy * dy).sqrt();
+
+            let (e, radius) = arc_geometry(chord_len, pt.curve_offset, curve_offset_in_bits);
+            if pt.curve_offset == 0
+                || chord_len < 1e-9
+                || e.abs() < 1e-9
+                || !radius.is_finite()
+                || radius > max_radius
+            {
+                line_to(d, m, x2, y2);
+            } else {
+                let (large_arc, sweep) = arc_flags(dx, dy, e);
+                arc_to(d, m, radius, large_arc, sweep, x2, y2);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Appends the geometry referenced by a reuse element, once per array
+    /// instance.
+    fn append_reuse<S: PathSink>(
+        &self,
+        d: &mut S,
+        m: &Affine,
+        reuse: &ReuseElement,
+        overrides: Option<&ElementAttributes>,
+        depth: usize,
+    ) -> WvgResult<()> {
+        if depth >= MAX_REUSE_DEPTH {
+            return Err(WvgError::ConversionError(format!(
+                "reuse elements nested more than {} deep",
+                MAX_REUSE_DEPTH
+            )));
+        }
+
+        let index = reuse.element_index as usize;
+        let max = self.document.elements.len().saturating_sub(1);
+        let target = self
+            .document
+            .elements
+            .get(index..)
+            .filter(|rest| !rest.is_empty())
+            .ok_or(WvgError::ElementIndexOutOfBounds {
+                index: reuse.element_index,
+                max,
+            })?;
+        let is_group = matches!(target[0].data, ElementData::GroupStart(_));
+        let target = if is_group { target } else { &target[..1] };
+
+        let base = m.then(&self.transform(&reuse.transform));
+        let instances = match &reuse.array_params {
+            Some(array) => {
+                self.add_instances(usize::from(array.columns) * usize::from(array.rows))?;
+                let width = array.width.unwrap_or(0);
+                let height = array.height.unwrap_or(width);
+                let mut instances = Vec::new();
+                for row in 0..array.rows {
+                    for col in 0..array.columns {
+                        let (tx, ty) = array_offset(col, row, width, height)?;
+                        instances.push(base.then(&Affine::translate(tx, ty)));
+                    }
+                }
+                instances
+            }
+            None => {
+                if depth > 0 {
+                    self.add_instances(1)?;
+                }
+                vec![base]
+            }
+        };
+
+        // Outer overrides win over those of this reuse element
+        let overrides = match (overrides, &reuse.override_attributes) {
+            (Some(outer), Some(inner)) => Some(outer.or(inner)),
+            (outer, inner) => outer.or(inner.as_ref()).cloned(),
+        };
+
+        let mut parts = Vec::new();
+        for instance in instances {
+            self.walk(target, instance, overrides.as_ref(), depth + 1, is_group, &mut parts)?;
+        }
+        for part in parts {
+            d.append(part);
+        }
+
+        Ok(())
+    }
+
+    /// Builds the affine transform for a WVG transform, matching the
+    /// `translate rotate scale` order used by the SVG converter.
+    pub(crate) fn transform(&self, t: &Transform) -> Affine {
+        let mut m = Affine::translate(
+            f64::from(t.translate_x.unwrap_or(0)),
+            f64::from(t.translate_y.unwrap_or(0)),
+        );
+
+        if let Some(angle) = t.angle {
+            let cx = f64::from(t.cx.unwrap_or(0));
+            let cy = f64::from(t.cy.unwrap_or(0));
+            let rotation = Affine::translate(cx, cy)
+                .then(&Affine::rotate(f64::from(angle) * self.angle_resolution))
+                .then(&Affine::translate(-cx, -cy));
+            m = m.then(&rotation);
+        }
+
+        let sx = t.scale_x.map(|v| 1.0 + f64::from(v) * self.scale_resolution);
+        let sy = t.scale_y.map(|v| 1.0 + f64::from(v) * self.scale_resolution);
+        match (sx, sy) {
+            (Some(sx), Some(sy)) => m = m.then(&Affine::scale(sx, sy)),
+            (Some(s), None) => m = m.then(&Affine::scale(s, s)),
+            _ => {}
+        }
+
+        m
+    }
+}
+
+/// Returns the offset of the array instance in column `col` and row `row`.
+pub(crate) fn array_offset(col: u8, row: u8, width: i32, height: i32) -> WvgResult<(f64, f64)> {
+    let tx = i32::from(col).checked_mul(width).ok_or(WvgError::CoordinateOverflow)?;
+    let ty = i32::from(row).checked_mul(height).ok_or(WvgError::CoordinateOverflow)?;
+    Ok((f64::from(tx), f64::from(ty)))
+}
+
+/// Checks that `grid` draws at most `limit` lines.
+pub(crate) fn check_grid_lines(grid: &GridShape, limit: usize) -> WvgResult<()> {
+    let lines = (grid.columns as usize)
+        .saturating_add(grid.rows as usize)
+        .saturating_add(2);
+    if lines > limit {
+        return Err(WvgError::LimitExceeded {
+            what: "grid line count",
+            value: lines,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that `star` has at most `limit` vertices.
+pub(crate) fn check_star_vertices(star: &StarShape, limit: usize) -> WvgResult<()> {
+    let vertices = (star.num_points as usize).saturating_mul(2);
+    if vertices > limit {
+        return Err(WvgError::LimitExceeded {
+            what: "star vertex count",
+            value: vertices,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Appends straight segments through `points`, optionally closing the path.
+fn append_lines(d: &mut impl PathSink, m: &Affine, points: &[(i32, i32)], close: bool) {
+    for (i, &(x, y)) in points.iter().enumerate() {
+        if i == 0 {
+            move_to(d, m, f64::from(x), f64::from(y));
+        } else {
+            line_to(d, m, f64::from(x), f64::from(y));
+        }
+    }
+    if close && !points.is_empty() {
+        d.close();
+    }
+}
+
+/// Appends a closed polygon through `vertices`.
+fn append_polygon(d: &mut impl PathSink, m: &Affine, vertices: &[(f64, f64)]) {
+    for (i, &(x, y)) in vertices.iter().enumerate() {
+        if i == 0 {
+            move_to(d, m, x, y);
+        } else {
+            line_to(d, m, x, y);
+        }
+    }
+    if !vertices.is_empty() {
+        d.close();
+    }
+}
+
+/// Appends a simple shape filling its bounding box, without its rotation.
+fn append_simple_shape(d: &mut impl PathSink, m: &Affine, ss: &SimpleShapeElement) {
+    match ss.shape_type {
+        SimpleShapeType::Rectangle => {
+            let (x, y) = (ss.origin.x, ss.origin.y);
+            let (right, bottom) = (x.saturating_add(ss.width), y.saturating_add(ss.height));
+            append_lines(d, m, &[(x, y), (right, y), (right, bottom), (x, bottom)], true);
+        }
+        SimpleShapeType::Ellipse => {
+            // A unit circle stretched onto the bounding box
+            let (cx, cy) = ss.center();
+            let m = m.then(&Affine::translate(cx, cy)).then(&Affine::scale(
+                f64::from(ss.width) / 2.0,
+                f64::from(ss.height) / 2.0,
+            ));
+            move_to(d, &m, -1.0, 0.0);
+            arc_to(d, &m, 1.0, 0, 1, 1.0, 0.0);
+            arc_to(d, &m, 1.0, 0, 1, -1.0, 0.0);
+            d.close();
+        }
+    }
+}
+
+/// Appends a grid as one subpath per line, or its outline for a single cell.
+///
+/// Grids drawing more than `max_lines` lines are rejected.
+fn append_grid(
+    d: &mut impl PathSink,
+    m: &Affine,
+    grid: &GridShape,
+    max_lines: usize,
+) -> WvgResult<()> {
+    if grid.columns == 0 || grid.rows == 0 {
+        return Ok(());
+    }
+
+    let grid_extent = |count: u32, size: i32| {
+        i32::try_from(count)
+            .ok()
+            .and_then(|count| count.checked_mul(size))
+            .ok_or(WvgError::CoordinateOverflow)
+    };
+    let x0 = grid.origin.x;
+    let y0 = grid.origin.y;
+    let x1 = x0.saturating_add(grid_extent(grid.columns, grid.cell_width)?);
+    let y1 = y0.saturating_add(grid_extent(grid.rows, grid.cell_height)?);
+
+    if grid.columns == 1 && grid.rows == 1 {
+        append_lines(d, m, &[(x0, y0), (x1, y0), (x1, y1), (x0, y1)], true);
+        return Ok(());
+    }
+
+    check_grid_lines(grid, max_lines)?;
+
+    let (x0, y0, x1, y1) = (f64::from(x0), f64::from(y0), f64::from(x1), f64::from(y1));
+    for row in 0..=grid.rows {
+        let y = y0 + f64::from(row) * f64::from(grid.cell_height);
+        move_to(d, m, x0, y);
+        line_to(d, m, x1, y);
+    }
+    for col in 0..=grid.columns {
+        let x = x0 + f64::from(col) * f64::from(grid.cell_width);
+        move_to(d, m, x, y0);
+        line_to(d, m, x, y1);
+    }
+
+    Ok(())
+}
+
+fn move_to(d: &mut impl PathSink, m: &Affine, x: f64, y: f64) {
+    let (x, y) = m.apply(x, y);
+    d.move_to(x, y);
+}
+
+fn line_to(d: &mut impl PathSink, m: &Affine, x: f64, y: f64) {
+    let (x, y) = m.apply(x, y);
+    d.line_to(x, y);
+}
+
+/// Appends a circular arc of the given radius, transformed into an
+/// elliptical arc.
+fn arc_to(d: &mut impl PathSink, m: &Affine, radius: f64, large_arc: u8, sweep: u8, x: f64, y: f64) {
+    let (rx, ry, rotation) = m.ellipse_axes();
+    let sweep = if m.flips() { 1 - sweep } else { sweep };
+    let (x, y) = m.apply(x, y);
+    d.arc_to(radius * rx, radius * ry, rotation, large_arc, sweep, x, y);
+}
+
+/// Formats a coordinate with at most three decimals.
+pub(crate) fn number(value: f64) -> String {
+    // Adding zero turns negative zero into zero
+    format!("{}", (value * 1000.0).round() / 1000.0 + 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affine_then_applies_inner_first() {
+        let m = Affine::translate(10.0, 0.0).then(&Affine::scale(2.0, 3.0));
+        assert_eq!(m.apply(1.0, 1.0), (12.0, 3.0));
+    }
+
+    #[test]
+    fn test_ellipse_axes() {
+        let (rx, ry, rotation) = Affine::scale(2.0, 3.0).ellipse_axes();
+        assert_eq!((rx, ry, rotation), (3.0, 2.0, 90.0));
+
+        let (rx, ry, rotation) = Affine::rotate(30.0).then(&Affine::scale(4.0, 4.0)).ellipse_axes();
+        assert!((rx - 4.0).abs() < 1e-9 && (ry - 4.0).abs() < 1e-9);
+        assert_eq!(rotation, 0.0);
+
+        let squash = Affine::rotate(30.0).then(&Affine::scale(1.0, 0.5));
+        let (rx, ry, rotation) = squash.ellipse_axes();
+        assert!((rx - 1.0).abs() < 1e-9 && (ry - 0.5).abs() < 1e-9);
+        assert!((rotation - 30.0).abs() < 1e-9);
+        assert!(!Affine::scale(2.0, 3.0).flips());
+        assert!(Affine::scale(-1.0, 1.0).flips());
+    }
+
+    #[test]
+    fn test_primitives_close_shape() {
+        let mut sink = Primitives::default();
+        let rectangle = SimpleShapeElement {
+            shape_type: SimpleShapeType::Rectangle,
+            attributes: ElementAttributes::default(),
+            origin: Point::new(0, 0),
+            width: 10,
+            height: 10,
+            rotation: None,
+        };
+        append_simple_shape(&mut sink, &Affine::IDENTITY, &rectangle);
+
+        assert_eq!(sink.primitives.len(), 4);
+        assert_eq!(
+            sink.primitives[3],
+            Primitive::Line {
+                from: (0.0, 10.0),
+                to: (0.0, 0.0),
+                stroke: Stroke::default(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        assert_eq!(number(83.0), "83");
+        assert_eq!(number(-0.0001), "0");
+        assert_eq!(number(1.23456), "1.235");
+    }
+}
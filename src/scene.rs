@@ -0,0 +1,563 @@
+//! Scene graph converter implementation for WVG documents.
+//!
+//! This module provides a `Converter` that turns a document into a retained
+//! scene graph of typed nodes, for GUI frameworks that draw with their own
+//! path API instead of parsing SVG text.
+
+use crate::converter::{Converter, ConverterConfig};
+use crate::error::{WvgError, WvgResult};
+use crate::log::{debug, trace};
+use crate::path::{array_offset, Affine, PathContext, PathSink, Stroke, MAX_REUSE_DEPTH};
+use crate::types::*;
+
+/// A retained scene graph of a WVG document.
+///
+/// Coordinates are drawing coordinates multiplied by the configured output
+/// scale. WVG has no raster content, so the scene only holds paths and
+/// groups. Local envelopes are not applied; the scene has no clipping.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    /// Width of the drawing.
+    pub width: f32,
+    /// Height of the drawing.
+    pub height: f32,
+    /// Background color, if the document declares one.
+    pub background: Option<Color>,
+    /// Top-level nodes in drawing order.
+    pub nodes: Vec<SceneNode>,
+}
+
+/// A node of a [`Scene`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneNode {
+    /// A drawable element.
+    Path(ScenePath),
+    /// A group, or an expanded reuse element.
+    Group(SceneGroup),
+}
+
+/// The geometry and style of a drawable element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenePath {
+    /// Id of the source element.
+    pub id: String,
+    /// Path commands in the coordinate system of the enclosing group.
+    pub commands: Vec<PathCommand>,
+    /// Effective stroke.
+    pub stroke: Stroke,
+    /// Fill color if the element is filled (BLACK if unset).
+    pub fill: Option<Color>,
+}
+
+/// A group of nodes sharing a transform.
+///
+/// Groups of the document keep their transform, visibility and opacity.
+/// Reuse elements become a group with the reuse transform around a copy of
+/// the referenced nodes, and arrays add one translated group per instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneGroup {
+    /// Id of the source element, with `_{row}_{col}` appended for array
+    /// instances.
+    pub id: String,
+    /// Affine transform `[a, b, c, d, e, f]` mapping `(x, y)` to
+    /// `(a * x + c * y + e, b * x + d * y + f)`, as in SVG's `matrix()`.
+    pub transform: [f32; 6],
+    /// Whether the group is displayed.
+    pub visible: bool,
+    /// Group opacity in `0.0..=1.0`.
+    pub opacity: f32,
+    /// Child nodes in drawing order.
+    pub children: Vec<SceneNode>,
+}
+
+/// A path command in absolute coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    /// Starts a new subpath.
+    MoveTo {
+        /// X coordinate of the point.
+        x: f32,
+        /// Y coordinate of the point.
+        y: f32,
+    },
+    /// Draws a straight line.
+    LineTo {
+        /// X coordinate of the point.
+        x: f32,
+        /// Y coordinate of the point.
+        y: f32,
+    },
+    /// Draws an elliptical arc, parameterised like an SVG `A` command.
+    ArcTo {
+        /// Semi-axes of the ellipse.
+        radii: (f32, f32),
+        /// Rotation of the ellipse's x-axis in degrees.
+        rotation: f32,
+        /// Whether the arc spans more than 180 degrees.
+        large_arc: bool,
+        /// Whether the arc is drawn in the positive-angle direction.
+        sweep: bool,
+        /// X coordinate of the end point.
+        x: f32,
+        /// Y coordinate of the end point.
+        y: f32,
+    },
+    /// Closes the current subpath.
+    Close,
+}
+
+/// Converter that produces a [`Scene`].
+///
+/// # Example
+///
+/// ```ignore
+/// use wvg::{BitStream, WvgParser, SceneConverter, Converter};
+///
+/// let data = std::fs::read("input.wvg")?;
+/// let mut bs = BitStream::new(&data);
+/// let document = WvgParser::new(&mut bs).parse()?;
+///
+/// let scene = SceneConverter::new().convert(&document)?;
+/// println!("{} top-level nodes", scene.nodes.len());
+/// ```
+pub struct SceneConverter {
+    /// Configuration options.
+    config: ConverterConfig,
+}
+
+impl SceneConverter {
+    /// Creates a new scene converter with default configuration.
+    pub fn new() -> Self {
+        Self {
+            config: ConverterConfig::default(),
+        }
+    }
+
+    /// Creates a new scene converter with the given configuration.
+    pub fn with_config(config: ConverterConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Default for SceneConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Converter for SceneConverter {
+    type Output = Scene;
+
+    fn convert(&self, document: &WvgDocument) -> WvgResult<Self::Output> {
+        let ctx = SceneContext {
+            document,
+            config: &self.config,
+            paths: PathContext::new(document, self.config.max_array_instances)?,
+        };
+
+        let nodes = ctx.nodes(&document.elements, None, 0, false)?;
+        debug!("Generated scene with {} top-level nodes", nodes.len());
+
+        let (width, height) = document.header.codec_params.coord_params.dimensions();
+        let scale = self.config.output_scale;
+        Ok(Scene {
+            width: (f64::from(width) * scale) as f32,
+            height: (f64::from(height) * scale) as f32,
+            background: document.header.color_config.background_color,
+            nodes,
+        })
+    }
+}
+
+/// Collects path commands.
+#[derive(Default)]
+struct Commands(Vec<PathCommand>);
+
+impl PathSink for Commands {
+    fn move_to(&mut self, x: f64, y: f64) {
+        self.0.push(PathCommand::MoveTo {
+            x: x as f32,
+            y: y as f32,
+        });
+    }
+
+    fn line_to(&mut self, x: f64, y: f64) {
+        self.0.push(PathCommand::LineTo {
+            x: x as f32,
+            y: y as f32,
+        });
+    }
+
+    fn arc_to(&mut self, rx: f64, ry: f64, rotation: f64, large_arc: u8, sweep: u8, x: f64, y: f64) {
+        self.0.push(PathCommand::ArcTo {
+            radii: (rx as f32, ry as f32),
+            rotation: rotation as f32,
+            large_arc: large_arc != 0,
+            sweep: sweep != 0,
+            x: x as f32,
+            y: y as f32,
+        });
+    }
+
+    fn close(&mut self) {
+        self.0.push(PathCommand::Close);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn append(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+}
+
+/// Internal context for scene generation.
+struct SceneContext<'a> {
+    /// The source document.
+    document: &'a WvgDocument,
+    /// Configuration options.
+    config: &'a ConverterConfig,
+    /// Geometry helpers shared with the path converter.
+    paths: PathContext<'a>,
+}
+
+impl SceneContext<'_> {
+    /// Builds the nodes for `elements`.
+    ///
+    /// `overrides` are the attributes of enclosing reuse elements. With
+    /// `single_group` set, stops once the group opened by the first element
+    /// is closed.
+    fn nodes(
+        &self,
+        elements: &[WvgElement],
+        overrides: Option<&ElementAttributes>,
+        depth: usize,
+        single_group: bool,
+    ) -> WvgResult<Vec<SceneNode>> {
+        let mut root = Vec::new();
+        // Open groups with the attributes their elements inherit
+        let mut stack: Vec<(SceneGroup, ElementAttributes)> = Vec::new();
+
+        for element in elements {
+            let inherited = stack.last().map(|(_, attrs)| attrs.clone()).unwrap_or_default();
+
+            let node = match &element.data {
+                ElementData::GroupStart(gs) => {
+                    let transform = gs
+                        .transform
+                        .as_ref()
+                        .map_or(Affine::IDENTITY, |t| self.paths.transform(t));
+                    let group = SceneGroup {
+                        id: element.id.clone(),
+                        transform: self.local(&transform),
+                        visible: gs.display,
+                        opacity: gs.opacity.unwrap_or(1.0).clamp(0.0, 1.0),
+                        children: Vec::new(),
+                    };
+                    stack.push((group, gs.attributes.or(&inherited)));
+                    None
+                }
+                ElementData::GroupEnd => stack.pop().map(|(group, _)| SceneNode::Group(group)),
+                ElementData::Reuse(reuse) => Some(self.reuse_node(element, reuse, overrides, depth)?),
+                _ => self.path_node(element, overrides, &inherited, depth)?,
+            };
+
+            if let Some(node) = node {
+                match stack.last_mut() {
+                    Some((parent, _)) => parent.children.push(node),
+                    None => root.push(node),
+                }
+                if single_group && stack.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        // Close groups left open at the end of the data
+        while let Some((group, _)) = stack.pop() {
+            match stack.last_mut() {
+                Some((parent, _)) => parent.children.push(SceneNode::Group(group)),
+                None => root.push(SceneNode::Group(group)),
+            }
+        }
+
+        Ok(root)
+    }
+
+    /// Builds the node for a drawable element, or nothing if it draws no
+    /// geometry.
+    fn path_node(
+        &self,
+        element: &WvgElement,
+        overrides: Option<&ElementAttributes>,
+        inherited: &ElementAttributes,
+        depth: usize,
+    ) -> WvgResult<Option<SceneNode>> {
+        trace!("Converting element: {}", element.id);
+
+        let scale = self.config.output_scale;
+        let mut commands = Commands::default();
+        self.paths
+            .append_element(&mut commands, element, &Affine::scale(scale, scale), overrides, depth)?;
+        if commands.is_empty() {
+            return Ok(None);
+        }
+
+        let attrs = element.data.attributes().cloned().unwrap_or_default();
+        let attrs = overrides.map_or(attrs.clone(), |o| o.or(&attrs)).or(inherited);
+        let attrs = self.document.header.color_config.resolve(&attrs);
+        let fill = (attrs.fill == Some(true)).then(|| attrs.fill_color.unwrap_or(Color::BLACK));
+
+        Ok(Some(SceneNode::Path(ScenePath {
+            id: element.id.clone(),
+            commands: commands.0,
+            stroke: Stroke::from(&attrs),
+            fill,
+        })))
+    }
+
+    /// Builds the group for a reuse element, holding a copy of the nodes it
+    /// references.
+    fn reuse_node(
+        &self,
+        element: &WvgElement,
+        reuse: &ReuseElement,
+        overrides: Option<&ElementAttributes>,
+        depth: usize,
+    ) -> WvgResult<SceneNode> {
+        if depth >= MAX_REUSE_DEPTH {
+            return Err(WvgError::ConversionError(format!(
+                "reuse elements nested more than {} deep",
+                MAX_REUSE_DEPTH
+            )));
+        }
+
+        let index = reuse.element_index as usize;
+        let target = self
+            .document
+            .elements
+            .get(index..)
+            .filter(|rest| !rest.is_empty())
+            .ok_or(WvgError::ElementIndexOutOfBounds {
+                index: reuse.element_index,
+                max: self.document.elements.len().saturating_sub(1),
+            })?;
+        let is_group = matches!(target[0].data, ElementData::GroupStart(_));
+        let target = if is_group { target } else { &target[..1] };
+
+        // Outer overrides win over those of this reuse element
+        let overrides = match (overrides, &reuse.override_attributes) {
+            (Some(outer), Some(inner)) => Some(outer.or(inner)),
+            (outer, inner) => outer.or(inner.as_ref()).cloned(),
+        };
+        if reuse.array_params.is_none() && depth > 0 {
+            self.paths.add_instances(1)?;
+        }
+        let before = self.paths.instance_count();
+        let children = self.nodes(target, overrides.as_ref(), depth + 1, is_group)?;
+
+        let children = match &reuse.array_params {
+            Some(array) => {
+                // Every instance copies the children along with the reuse
+                // instances already expanded inside them
+                let instances = usize::from(array.columns) * usize::from(array.rows);
+                let nested = self.paths.instance_count() - before;
+                self.paths.add_instances(instances.saturating_mul(nested + 1))?;
+
+                let width = array.width.unwrap_or(0);
+                let height = array.height.unwrap_or(width);
+                let mut groups = Vec::with_capacity(instances);
+                for row in 0..array.rows {
+                    for col in 0..array.columns {
+                        let (tx, ty) = array_offset(col, row, width, height)?;
+                        let offset = Affine::translate(tx, ty);
+                        groups.push(SceneNode::Group(SceneGroup {
+                            id: format!("{}_{}_{}", element.id, row, col),
+                            transform: self.local(&offset),
+                            visible: true,
+                            opacity: 1.0,
+                            children: children.clone(),
+                        }));
+                    }
+                }
+                groups
+            }
+            None => children,
+        };
+
+        Ok(SceneNode::Group(SceneGroup {
+            id: element.id.clone(),
+            transform: self.local(&self.paths.transform(&reuse.transform)),
+            visible: true,
+            opacity: 1.0,
+            children,
+        }))
+    }
+
+    /// Converts a transform in drawing coordinates into one for the scaled
+    /// coordinates of the scene.
+    fn local(&self, m: &Affine) -> [f32; 6] {
+        let scale = self.config.output_scale;
+        [
+            m.a as f32,
+            m.b as f32,
+            m.c as f32,
+            m.d as f32,
+            (m.e * scale) as f32,
+            (m.f * scale) as f32,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(elements: Vec<WvgElement>) -> WvgDocument {
+        WvgDocument {
+            header: WvgHeader {
+                general_info: GeneralInfo::default(),
+                color_config: ColorConfig::default(),
+                codec_params: CodecParams {
+                    element_masks: vec![false; 8],
+                    attribute_masks: AttributeMasks::default(),
+                    generic_params: GenericParams::default(),
+                    coord_params: CoordinateParams::Compact(CompactCoordinateParams::default()),
+                },
+                animation_mode: None,
+            },
+            elements,
+        }
+    }
+
+    fn element(id: &str, data: ElementData) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data,
+        }
+    }
+
+    fn polyline(id: &str, points: &[(i32, i32)]) -> WvgElement {
+        element(
+            id,
+            ElementData::Polyline(PolylineElement {
+                attributes: ElementAttributes::default(),
+                points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            }),
+        )
+    }
+
+    fn array_reuse(id: &str, element_index: u32, columns: u8, rows: u8) -> WvgElement {
+        element(
+            id,
+            ElementData::Reuse(ReuseElement {
+                element_index,
+                transform: Transform::default(),
+                array_params: Some(ArrayParams {
+                    columns,
+                    rows,
+                    width: Some(1),
+                    height: None,
+                }),
+                override_attributes: None,
+            }),
+        )
+    }
+
+    #[test]
+    fn test_nested_array_limit() {
+        // Each array is within the limit, but the copies multiply
+        let doc = document(vec![
+            polyline("el_0", &[(0, 0), (1, 0)]),
+            array_reuse("el_1", 0, 60, 60),
+            array_reuse("el_2", 1, 60, 60),
+            array_reuse("el_3", 2, 60, 60),
+        ]);
+        assert!(matches!(
+            SceneConverter::new().convert(&doc),
+            Err(WvgError::LimitExceeded { what: "reuse instance count", limit: 4096, .. })
+        ));
+
+        // el_1 expands to 9 instances on its own and again inside el_2,
+        // whose 4 instances each copy those 9
+        let doc = document(vec![
+            polyline("el_0", &[(0, 0), (1, 0)]),
+            array_reuse("el_1", 0, 3, 3),
+            array_reuse("el_2", 1, 2, 2),
+        ]);
+        let config = ConverterConfig::new().with_max_array_instances(9 + 9 + 4 * 10);
+        assert!(SceneConverter::with_config(config.clone()).convert(&doc).is_ok());
+        let config = config.with_max_array_instances(9 + 9 + 4 * 10 - 1);
+        assert!(SceneConverter::with_config(config).convert(&doc).is_err());
+    }
+
+    #[test]
+    fn test_groups_and_arrays() {
+        let group = element(
+            "el_0",
+            ElementData::GroupStart(GroupStartElement {
+                transform: Some(Transform {
+                    translate_x: Some(5),
+                    ..Default::default()
+                }),
+                display: false,
+                opacity: None,
+                attributes: ElementAttributes::default(),
+            }),
+        );
+        let reuse = element(
+            "el_3",
+            ElementData::Reuse(ReuseElement {
+                element_index: 1,
+                transform: Transform::default(),
+                array_params: Some(ArrayParams {
+                    columns: 2,
+                    rows: 1,
+                    width: Some(10),
+                    height: None,
+                }),
+                override_attributes: None,
+            }),
+        );
+        let doc = document(vec![
+            group,
+            polyline("el_1", &[(0, 0), (2, 0)]),
+            element("el_2", ElementData::GroupEnd),
+            reuse,
+        ]);
+
+        let config = ConverterConfig::new().with_output_scale(2.0);
+        let scene = SceneConverter::with_config(config).convert(&doc).unwrap();
+        assert_eq!(scene.nodes.len(), 2);
+
+        let SceneNode::Group(group) = &scene.nodes[0] else {
+            panic!("expected a group");
+        };
+        assert_eq!(group.transform, [1.0, 0.0, 0.0, 1.0, 10.0, 0.0]);
+        assert!(!group.visible);
+        assert_eq!(
+            group.children,
+            vec![SceneNode::Path(ScenePath {
+                id: "el_1".to_string(),
+                commands: vec![PathCommand::MoveTo { x: 0.0, y: 0.0 }, PathCommand::LineTo { x: 4.0, y: 0.0 }],
+                stroke: Stroke::default(),
+                fill: None,
+            })]
+        );
+
+        let SceneNode::Group(reuse) = &scene.nodes[1] else {
+            panic!("expected a group");
+        };
+        let ids: Vec<_> = reuse
+            .children
+            .iter()
+            .map(|node| match node {
+                SceneNode::Group(instance) => (instance.id.as_str(), instance.transform[4]),
+                SceneNode::Path(_) => panic!("expected array instances"),
+            })
+            .collect();
+        assert_eq!(ids, vec![("el_3_0_0", 0.0), ("el_3_0_1", 20.0)]);
+    }
+}
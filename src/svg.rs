@@ -3,12 +3,15 @@
 //! This module provides a concrete implementation of the `Converter` trait
 //! that outputs SVG (Scalable Vector Graphics) format.
 
+use std::collections::HashMap;
 use std::fmt::Write;
 
-use crate::converter::{Converter, ConverterConfig};
-use crate::error::WvgResult;
+use crate::converter::{Converter, ConverterConfig, DataUriEncoding, FillRule, HiddenMode};
+use crate::error::{ParseWarning, WvgError, WvgResult};
+use crate::html::escape_html;
+use crate::path::{check_grid_lines, check_star_vertices, number};
 use crate::types::*;
-use tracing::{debug, trace};
+use crate::log::{debug, trace, warn};
 
 /// Converter that produces SVG output from WVG documents.
 ///
@@ -46,6 +49,33 @@ impl SvgConverter {
     pub fn with_config(config: ConverterConfig) -> Self {
         Self { config }
     }
+
+    /// Converts the document like `convert`, additionally returning the
+    /// problems that were worked around while generating the output.
+    pub fn convert_with_warnings(
+        &self,
+        document: &WvgDocument,
+    ) -> WvgResult<(String, Vec<ParseWarning>)> {
+        let mut ctx = SvgContext::new(document, &self.config);
+        let svg = ctx.generate()?;
+        Ok((svg, ctx.warnings))
+    }
+
+    /// Converts the document to a `data:image/svg+xml` URI, e.g. for use in
+    /// an `<img src>` or a CSS `url()`.
+    ///
+    /// The SVG is encoded as configured by `data_uri_encoding`.
+    pub fn convert_data_uri(&self, document: &WvgDocument) -> WvgResult<String> {
+        let svg = self.convert(document)?;
+        Ok(match self.config.data_uri_encoding {
+            DataUriEncoding::Percent => {
+                format!("data:image/svg+xml;charset=utf-8,{}", percent_encode(&svg))
+            }
+            DataUriEncoding::Base64 => {
+                format!("data:image/svg+xml;base64,{}", base64_encode(svg.as_bytes()))
+            }
+        })
+    }
 }
 
 impl Default for SvgConverter {
@@ -73,21 +103,64 @@ struct SvgContext<'a> {
     output: String,
     /// Indentation level.
     indent: usize,
-    /// Group stack for tracking nested groups.
-    group_stack: Vec<bool>,
+    /// Group stack for tracking nested groups; each entry records whether
+    /// the group is hidden, either itself or through an ancestor, and the
+    /// attributes its elements inherit.
+    group_stack: Vec<(bool, ElementAttributes)>,
+    /// Number of groups started so far, used for ARIA labels.
+    group_count: usize,
+    /// Group depth at which each open local envelope was started; its
+    /// clipped group is closed with the enclosing group.
+    envelope_stack: Vec<usize>,
+    /// Index of the element currently being written.
+    element_index: usize,
+    /// CSS class index for each distinct style declaration (if enabled).
+    style_classes: HashMap<String, usize>,
     /// Angle resolution.
     angle_resolution: f64,
     /// Scale resolution.
     scale_resolution: f64,
+    /// Factor applied to all emitted coordinates.
+    scale: f64,
+    /// Top-left corner of the drawing (negative for signed coordinates).
+    origin: (i32, i32),
+    /// Drawing size, with zero dimensions replaced by the content extent.
+    size: (u16, u16),
+    /// Problems worked around during generation.
+    warnings: Vec<ParseWarning>,
 }
 
 impl<'a> SvgContext<'a> {
     /// Creates a new SVG generation context.
     fn new(document: &'a WvgDocument, config: &'a ConverterConfig) -> Self {
-        // Calculate resolutions from generic params
         let gp = &document.header.codec_params.generic_params;
-        let angle_resolution = 22.5 / f64::from(1 << gp.angle_resolution);
-        let scale_resolution = 0.25 / f64::from(1 << gp.scale_resolution);
+        let angle_resolution = gp.angle_degrees();
+        let scale_resolution = gp.scale_step();
+
+        let (min, max) = content_bounds(document);
+        let origin = match &document.header.codec_params.coord_params {
+            CoordinateParams::Flat(params) if !params.xy_all_positive && !config.clamp_to_bounds => {
+                min
+            }
+            _ => (0, 0),
+        };
+
+        let mut warnings = Vec::new();
+        let (mut width, mut height) = document.header.codec_params.coord_params.dimensions();
+        if width == 0 || height == 0 {
+            warn!(
+                "Drawing size {}x{} has a zero dimension, using the content extent",
+                width, height
+            );
+            warnings.push(ParseWarning::ZeroDimension);
+            let extent = |value: i32| u16::try_from(value.max(1)).unwrap_or(u16::MAX);
+            if width == 0 {
+                width = extent(max.0);
+            }
+            if height == 0 {
+                height = extent(max.1);
+            }
+        }
 
         Self {
             document,
@@ -95,19 +168,91 @@ impl<'a> SvgContext<'a> {
             output: String::with_capacity(4096),
             indent: 0,
             group_stack: Vec::new(),
+            group_count: 0,
+            envelope_stack: Vec::new(),
+            element_index: 0,
+            style_classes: HashMap::new(),
             angle_resolution,
             scale_resolution,
+            scale: config.output_scale,
+            origin,
+            size: (width, height),
+            warnings,
         }
     }
 
     /// Generates the complete SVG document.
     fn generate(&mut self) -> WvgResult<String> {
+        let angle_resolution = self.document.header.codec_params.generic_params.angle_resolution;
+        if angle_resolution > 3 {
+            return Err(WvgError::InvalidAngleResolution(angle_resolution));
+        }
+
+        if self.config.use_css_classes {
+            self.collect_style_classes();
+        }
         self.write_header();
+        if let Some(root) = self.config.root_transform {
+            self.write_line(&format!(
+                "<g transform=\"translate({}, {}) scale({})\">",
+                root.translate_x, root.translate_y, root.scale
+            ));
+            self.indent += 1;
+        }
         self.write_elements()?;
+        if self.config.root_transform.is_some() {
+            self.indent -= 1;
+            self.write_line("</g>");
+        }
         self.write_footer();
         Ok(std::mem::take(&mut self.output))
     }
 
+    /// Applies the output scale to a coordinate value, rounded to the snap
+    /// grid if one is set.
+    fn scaled(&self, value: i32) -> f64 {
+        self.snap(f64::from(value) * self.scale)
+    }
+
+    /// Rounds an output coordinate to the snap grid if one is set.
+    fn snap(&self, value: f64) -> f64 {
+        match self.snap_grid() {
+            // Adding zero turns -0 into 0
+            Some(grid) => (value / grid).round() * grid + 0.0,
+            None => value,
+        }
+    }
+
+    /// Returns the scaled offset from `from` to `to`. With a snap grid this
+    /// is the difference of the rounded positions, so relative commands end
+    /// on the same points as absolute ones.
+    fn scaled_offset(&self, from: i32, to: i32) -> f64 {
+        if self.snap_grid().is_some() {
+            self.scaled(to) - self.scaled(from)
+        } else {
+            self.scaled(to - from)
+        }
+    }
+
+    fn snap_grid(&self) -> Option<f64> {
+        self.config.snap_grid.filter(|grid| *grid > 0.0)
+    }
+
+    /// Returns the drawing size, falling back to the content extent for
+    /// zero dimensions.
+    fn drawing_size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Clamps a point into the drawing bounds if `clamp_to_bounds` is set.
+    fn bounded(&self, x: i32, y: i32) -> (i32, i32) {
+        if !self.config.clamp_to_bounds {
+            return (x, y);
+        }
+        let (width, height) = self.drawing_size();
+        (x.clamp(0, i32::from(width)), y.clamp(0, i32::from(height)))
+    }
+
     /// Writes a line with proper indentation.
     fn write_line(&mut self, line: &str) {
         if self.config.pretty_print {
@@ -123,18 +268,62 @@ impl<'a> SvgContext<'a> {
 
     /// Writes the SVG header.
     fn write_header(&mut self) {
-        let (width, height) = match &self.document.header.codec_params.coord_params {
-            CoordinateParams::Flat(params) => (params.drawing_width, params.drawing_height),
-            CoordinateParams::Compact(_) => (100, 100), // Fallback
+        let (width, height) = self.drawing_size();
+        let (ox, oy) = self.origin;
+
+        let padding = self.config.padding;
+        let view_width = (f64::from(width) - f64::from(ox)) * self.scale + 2.0 * padding;
+        let view_height = (f64::from(height) - f64::from(oy)) * self.scale + 2.0 * padding;
+
+        let size = if self.config.max_dimension.is_some() {
+            let (w, h) = self.config.fit_size(view_width, view_height);
+            format!(" width=\"{}\" height=\"{}\"", w, h)
+        } else {
+            String::new()
+        };
+
+        let version = self.document.header.general_info.version;
+        let version_attribute = if self.config.emit_data_attributes {
+            format!(" data-wvg-version=\"{}\"", version)
+        } else {
+            String::new()
+        };
+
+        let aria = if self.config.emit_aria {
+            match &self.document.header.general_info.title {
+                Some(title) => format!(" role=\"img\" aria-label=\"{}\"", escape_html(title)),
+                None => " role=\"img\"".to_string(),
+            }
+        } else {
+            String::new()
         };
 
-        self.write_line("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        let xlink = if self.config.xlink_compat {
+            " xmlns:xlink=\"http://www.w3.org/1999/xlink\""
+        } else {
+            ""
+        };
+
+        if self.config.emit_xml_declaration && !self.config.minify {
+            self.write_line("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        }
         self.write_line(&format!(
-            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {} {}\">",
-            width, height
+            "<svg xmlns=\"http://www.w3.org/2000/svg\"{}{} viewBox=\"{} {} {} {}\"{}{}>",
+            xlink,
+            size,
+            self.scaled(ox) - padding,
+            self.scaled(oy) - padding,
+            view_width,
+            view_height,
+            version_attribute,
+            aria
         ));
         self.indent += 1;
 
+        if self.config.include_comments {
+            self.write_line(&format!("<!-- WVG version {} -->", version));
+        }
+
         // Write default styles
         self.write_default_styles();
     }
@@ -144,58 +333,135 @@ impl<'a> SvgContext<'a> {
         let cc = &self.document.header.color_config;
 
         // Background rectangle if color is set
-        if let Some(bg) = &cc.background_color {
-            let (width, height) = match &self.document.header.codec_params.coord_params {
-                CoordinateParams::Flat(params) => (params.drawing_width, params.drawing_height),
-                CoordinateParams::Compact(_) => (100, 100),
+        if let Some(bg) = self.config.background_color.as_ref().or(cc.background_color.as_ref()) {
+            let (width, height) = self.drawing_size();
+
+            let opacity = if bg.is_opaque() {
+                String::new()
+            } else {
+                format!(" fill-opacity=\"{}\"", alpha_to_opacity(bg.a))
+            };
+
+            let (ox, oy) = self.origin;
+            let position = if (ox, oy) == (0, 0) {
+                String::new()
+            } else {
+                format!("x=\"{}\" y=\"{}\" ", self.scaled(ox), self.scaled(oy))
             };
 
             self.write_line(&format!(
-                "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
-                width,
-                height,
-                color_to_hex(bg)
+                "<rect {}width=\"{}\" height=\"{}\" fill=\"{}\"{}/>",
+                position,
+                (f64::from(width) - f64::from(ox)) * self.scale,
+                (f64::from(height) - f64::from(oy)) * self.scale,
+                self.hex(bg),
+                opacity
             ));
         }
 
-        // Define default styles in defs
-        self.write_line("<defs>");
-        self.indent += 1;
-
         // Default stroke color
-        let stroke = cc
-            .default_line_color
-            .as_ref()
-            .map(color_to_hex)
-            .unwrap_or_else(|| "#000000".to_string());
+        let mut stroke = self.hex(cc.default_line_color.as_ref().unwrap_or(&Color::BLACK));
+        if let Some(color) = cc.default_line_color.filter(|c| !c.is_opaque()) {
+            write!(&mut stroke, "; stroke-opacity: {}", alpha_to_opacity(color.a)).unwrap();
+        }
 
         // Default fill color
-        let fill = cc
+        let mut fill = cc
             .default_fill_color
             .as_ref()
-            .map(color_to_hex)
+            .map(|c| self.hex(c))
             .unwrap_or_else(|| "none".to_string());
+        if let Some(color) = cc.default_fill_color.filter(|c| !c.is_opaque()) {
+            write!(&mut fill, "; fill-opacity: {}", alpha_to_opacity(color.a)).unwrap();
+        }
+
+        // Default stroke width, scaled like explicit line widths
+        let stroke_width = self.config.line_width_scale.unwrap_or(1.0) * self.scale as f32;
+
+        // Stars are the only polygons, so only list the selector when needed
+        let has_stars = self.document.elements.iter().any(|element| {
+            matches!(
+                &element.data,
+                ElementData::SpecialShape(SpecialShapeElement { shape: SpecialShape::Star(_), .. })
+            )
+        });
+        let polygon = if has_stars { ",polygon" } else { "" };
+
+        let mut class_rules: Vec<(&String, &usize)> = self.style_classes.iter().collect();
+        class_rules.sort_by_key(|&(_, &index)| index);
+        let mut classes = String::new();
+        for (declarations, index) in class_rules {
+            if self.config.minify {
+                write!(&mut classes, ".s{}{{{}}}", index + 1, declarations).unwrap();
+            } else {
+                write!(&mut classes, " .s{} {{ {}; }}", index + 1, declarations).unwrap();
+            }
+        }
+
+        if self.config.minify {
+            self.write_line(&format!(
+                "<style>path,polyline,line,circle,ellipse,rect{}{{stroke:{};fill:{};stroke-width:{}}}{}</style>",
+                polygon,
+                stroke.replace(": ", ":").replace("; ", ";"),
+                fill.replace(": ", ":").replace("; ", ";"),
+                stroke_width,
+                classes
+            ));
+            return;
+        }
+
+        // Define default styles in defs
+        self.write_line("<defs>");
+        self.indent += 1;
 
         self.write_line(&format!(
-            "<style>path, polyline, line, circle, ellipse, rect {{ stroke: {}; fill: {}; stroke-width: 1; }}</style>",
-            stroke, fill
+            "<style>path, polyline, line, circle, ellipse, rect{} {{ stroke: {}; fill: {}; stroke-width: {}; }}{}</style>",
+            polygon.replace(',', ", "),
+            stroke,
+            fill,
+            stroke_width,
+            classes
         ));
 
         self.indent -= 1;
         self.write_line("</defs>");
     }
 
+    /// Formats a color, using the short `#rgb` form when minifying.
+    fn hex(&self, color: &Color) -> String {
+        if self.config.minify {
+            color_to_short_hex(color)
+        } else {
+            color_to_hex(color)
+        }
+    }
+
+    /// Strips unnecessary whitespace from path data when minifying, or
+    /// wraps it if `wrap_path_data` is set.
+    fn path_data(&self, d: String) -> String {
+        if self.config.minify {
+            minify_path_data(&d)
+        } else if let Some(column) = self.config.wrap_path_data {
+            wrap_path_data(&d, column)
+        } else {
+            d
+        }
+    }
+
     /// Writes all elements to the SVG.
     fn write_elements(&mut self) -> WvgResult<()> {
-        for element in &self.document.elements {
+        for (i, element) in self.document.elements.iter().enumerate() {
+            self.element_index = i;
             self.write_element(element)?;
         }
 
-        // Close any remaining groups
+        // Close any remaining envelopes and groups
+        self.close_envelopes();
         while !self.group_stack.is_empty() {
             self.group_stack.pop();
             self.indent -= 1;
             self.write_line("</g>");
+            self.close_envelopes();
         }
 
         Ok(())
@@ -209,9 +475,11 @@ impl<'a> SvgContext<'a> {
             ElementData::Polyline(pl) => self.write_polyline(element, pl),
             ElementData::CircularPolyline(cp) => self.write_circular_polyline(element, cp),
             ElementData::SimpleShape(ss) => self.write_simple_shape(element, ss),
+            ElementData::SpecialShape(ss) => self.write_special_shape(element, ss),
             ElementData::Reuse(reuse) => self.write_reuse(element, reuse),
             ElementData::GroupStart(gs) => self.write_group_start(element, gs),
             ElementData::GroupEnd => self.write_group_end(),
+            ElementData::LocalEnvelope(envelope) => self.write_local_envelope(element, envelope),
         }
     }
 
@@ -225,33 +493,32 @@ impl<'a> SvgContext<'a> {
 
         let style = self.build_style(&pl.attributes);
 
+        let points: Vec<(i32, i32)> = pl.points.iter().map(|p| self.bounded(p.x, p.y)).collect();
+
         // Single point = draw a small circle (dot)
-        if pl.points.len() == 1 {
-            let p = &pl.points[0];
+        if points.len() == 1 {
+            let (x, y) = points[0];
             self.write_line(&format!(
-                "<circle id=\"{}\" cx=\"{}\" cy=\"{}\" r=\"1.0\" {}/>",
-                element.id, p.x, p.y, style
+                "<circle id=\"{}\"{} cx=\"{}\" cy=\"{}\" r=\"{:.1}\" {}/>",
+                self.prefixed_id(&element.id),
+                self.data_attributes(element),
+                self.scaled(x),
+                self.scaled(y),
+                self.scale,
+                style
             ));
             return Ok(());
         }
 
         // Multiple points = path with line segments
-        let mut path_data = String::new();
-        for (i, point) in pl.points.iter().enumerate() {
-            if i == 0 {
-                write!(&mut path_data, "M {} {}", point.x, point.y).unwrap();
-            } else {
-                // Use relative offsets like Python version
-                let prev = &pl.points[i - 1];
-                let dx = point.x - prev.x;
-                let dy = point.y - prev.y;
-                write!(&mut path_data, " l {} {}", dx, dy).unwrap();
-            }
-        }
+        let path_data = self.line_path_data(&points);
 
         self.write_line(&format!(
-            "<path id=\"{}\" d=\"{}\" {}/>",
-            element.id, path_data, style
+            "<path id=\"{}\"{} d=\"{}\" {}/>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element),
+            self.path_data(path_data),
+            style
         ));
 
         Ok(())
@@ -273,94 +540,247 @@ impl<'a> SvgContext<'a> {
             return Ok(());
         }
 
-        // Convert relative points to absolute and track current position
-        let mut path_data = String::new();
-        let mut current_x = 0i32;
-        let mut current_y = 0i32;
+        // Convert relative points to absolute. Relative offsets accumulate
+        // from the unclamped position, while the emitted segments run
+        // between clamped positions.
+        let mut points = Vec::with_capacity(cp.points.len());
+        let mut current = Point::new(0, 0);
 
-        for (i, pt) in cp.points.iter().enumerate() {
-            let (target_x, target_y) = if pt.is_absolute || i < 2 {
-                (pt.point.x, pt.point.y)
+        for pt in &cp.points {
+            current = if pt.is_absolute {
+                pt.point
             } else {
-                (current_x + pt.point.x, current_y + pt.point.y)
+                current
+                    .checked_offset(pt.point.x, pt.point.y)
+                    .ok_or(WvgError::CoordinateOverflow)?
             };
+            points.push(self.bounded(current.x, current.y));
+        }
 
-            if i == 0 {
-                // Move to first point
-                write!(&mut path_data, "M {} {}", target_x, target_y).unwrap();
-            } else {
-                let offset_val = pt.curve_offset;
-
-                if offset_val == 0 {
+        // Without any curves this is a plain polyline, so emit it the same way
+        let path_data = if cp.points.iter().skip(1).all(|pt| pt.curve_offset == 0) {
+            self.line_path_data(&points)
+        } else {
+            let (first_x, first_y) = points[0];
+            let mut path_data = format!("M {} {}", self.scaled(first_x), self.scaled(first_y));
+            for (pt, pair) in cp.points.iter().skip(1).zip(points.windows(2)) {
+                let ((from_x, from_y), (to_x, to_y)) = (pair[0], pair[1]);
+                if pt.curve_offset == 0 {
                     // Straight line
-                    write!(&mut path_data, " L {} {}", target_x, target_y).unwrap();
+                    let line = self.segment('L', "", from_x, from_y, to_x, to_y);
+                    write!(&mut path_data, " {}", line).unwrap();
                 } else {
                     // Arc segment
-                    let arc_str = self.compute_arc_command(
-                        current_x, current_y,
-                        target_x, target_y,
-                        offset_val,
-                    );
+                    let arc_str =
+                        self.compute_arc_command(from_x, from_y, to_x, to_y, pt.curve_offset);
                     write!(&mut path_data, " {}", arc_str).unwrap();
                 }
             }
-
-            current_x = target_x;
-            current_y = target_y;
-        }
+            path_data
+        };
 
         let style = self.build_style(&cp.attributes);
         self.write_line(&format!(
-            "<path id=\"{}\" d=\"{}\" {}/>",
-            element.id, path_data, style
+            "<path id=\"{}\"{} d=\"{}\" {}/>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element),
+            self.path_data(path_data),
+            style
         ));
 
         Ok(())
     }
 
+    /// Builds path data for straight segments through the given points.
+    fn line_path_data(&self, points: &[(i32, i32)]) -> String {
+        let mut path_data = String::new();
+        for (i, &(x, y)) in points.iter().enumerate() {
+            if i == 0 {
+                write!(&mut path_data, "M {} {}", self.scaled(x), self.scaled(y)).unwrap();
+            } else if self.config.absolute_paths {
+                write!(&mut path_data, " L {} {}", self.scaled(x), self.scaled(y)).unwrap();
+            } else {
+                // Use relative offsets like Python version
+                let (prev_x, prev_y) = points[i - 1];
+                let dx = self.scaled_offset(prev_x, x);
+                let dy = self.scaled_offset(prev_y, y);
+                write!(&mut path_data, " l {} {}", dx, dy).unwrap();
+            }
+        }
+        path_data
+    }
+
     /// Computes an SVG arc command from two points and a curve offset.
     /// 
     /// Based on the WVG specification for circular polylines, where the curve
-    /// offset determines the arc radius and direction.
-    fn compute_arc_command(&self, x1: i32, y1: i32, x2: i32, y2: i32, offset: i32) -> String {
+    /// offset determines the arc radius and direction. Arcs whose radius
+    /// exceeds `MAX_RADIUS_FACTOR` times the drawing size are drawn as lines.
+    fn compute_arc_command(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, offset: i32) -> String {
         let dx = (x2 - x1) as f64;
         let dy = (y2 - y1) as f64;
         let chord_len = (dx * dx + dy * dy).sqrt();
 
         if chord_len < 1e-9 {
-            return format!("L {} {}", x2, y2);
+            return self.segment('L', "", x1, y1, x2, y2);
         }
 
-        // Calculate arc parameters from curve offset
-        // n is 4 or 5 bits based on curve_offset_in_bits setting
-        let n = if self.document.header.codec_params.generic_params.curve_offset_in_bits.unwrap_or(0) == 1 {
-            5
+        let curve_offset_in_bits =
+            self.document.header.codec_params.generic_params.curve_offset_in_bits;
+        let (e, radius) = arc_geometry(chord_len, offset, curve_offset_in_bits);
+
+        if e.abs() < 1e-9 {
+            return self.segment('L', "", x1, y1, x2, y2);
+        }
+
+        let (width, height) = self.drawing_size();
+        let max_radius = MAX_RADIUS_FACTOR * f64::from(width.max(height).max(1));
+        if !radius.is_finite() || radius > max_radius {
+            warn!("Degenerate arc with radius {:.2} drawn as a line", radius);
+            self.warnings.push(ParseWarning::DegenerateArc {
+                element_index: self.element_index,
+            });
+            return self.segment('L', "", x1, y1, x2, y2);
+        }
+        let radius = radius * self.scale;
+
+        let (large_arc, sweep) = arc_flags(dx, dy, e);
+        let args = format!(" {:.2} {:.2} 0 {} {}", radius, radius, large_arc, sweep);
+        self.segment('A', &args, x1, y1, x2, y2)
+    }
+
+    /// Formats a circular polyline segment from `command`, its leading
+    /// `args` and the end point, as a relative command if `relative_arcs`
+    /// applies.
+    fn segment(&self, command: char, args: &str, x1: i32, y1: i32, x2: i32, y2: i32) -> String {
+        if self.config.relative_arcs && !self.config.absolute_paths {
+            format!(
+                "{}{} {} {}",
+                command.to_ascii_lowercase(),
+                args,
+                self.scaled_offset(x1, x2),
+                self.scaled_offset(y1, y2)
+            )
         } else {
-            4
+            format!("{}{} {} {}", command, args, self.scaled(x2), self.scaled(y2))
+        }
+    }
+
+    /// Writes a special shape element.
+    fn write_special_shape(
+        &mut self,
+        element: &WvgElement,
+        ss: &SpecialShapeElement,
+    ) -> WvgResult<()> {
+        let style = self.build_style(&ss.attributes);
+
+        match &ss.shape {
+            SpecialShape::Grid(grid) => self.write_grid(element, grid, &style),
+            SpecialShape::Star(star) => self.write_star(element, star, &style),
+        }
+    }
+
+    /// Writes a star as a `<polygon>`.
+    fn write_star(&mut self, element: &WvgElement, star: &StarShape, style: &str) -> WvgResult<()> {
+        debug!("Writing star {} with {} points", element.id, star.num_points);
+
+        check_star_vertices(star, self.config.max_array_instances)?;
+        let vertices = star.vertices(self.angle_resolution);
+        if vertices.is_empty() {
+            return Ok(());
+        }
+
+        let points: Vec<String> = vertices
+            .iter()
+            .map(|&(x, y)| {
+                let x = self.snap(x * self.scale);
+                let y = self.snap(y * self.scale);
+                format!("{},{}", number(x), number(y))
+            })
+            .collect();
+        self.write_line(&format!(
+            "<polygon id=\"{}\"{} points=\"{}\" {}/>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element),
+            points.join(" "),
+            style
+        ));
+
+        Ok(())
+    }
+
+    /// Writes a grid as a group of horizontal and vertical lines.
+    ///
+    /// The style is applied to every line rather than the group, since the
+    /// default stylesheet targets `line` directly and would win over
+    /// inherited properties.
+    fn write_grid(&mut self, element: &WvgElement, grid: &GridShape, style: &str) -> WvgResult<()> {
+        debug!(
+            "Writing grid {}: {}x{} cells",
+            element.id, grid.columns, grid.rows
+        );
+
+        if grid.columns == 0 || grid.rows == 0 {
+            return Ok(());
+        }
+
+        let grid_extent = |count: u32, size: i32| {
+            i32::try_from(count)
+                .ok()
+                .and_then(|count| count.checked_mul(size))
+                .ok_or(WvgError::CoordinateOverflow)
         };
-        let k = ((1 << n) - 2) as f64;
+        let width = grid_extent(grid.columns, grid.cell_width)?;
+        let height = grid_extent(grid.rows, grid.cell_height)?;
 
-        let r = offset as f64 / k;
-        let e = r * chord_len;
+        let x0 = self.scaled(grid.origin.x);
+        let y0 = self.scaled(grid.origin.y);
+        let x1 = x0 + self.scaled(width);
+        let y1 = y0 + self.scaled(height);
 
-        if e.abs() < 1e-9 {
-            return format!("L {} {}", x2, y2);
+        // A single cell is just its outline
+        if grid.columns == 1 && grid.rows == 1 {
+            self.write_line(&format!(
+                "<rect id=\"{}\"{} x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                self.prefixed_id(&element.id),
+                self.data_attributes(element),
+                x0,
+                y0,
+                self.scaled(width),
+                self.scaled(height),
+                style
+            ));
+            return Ok(());
         }
 
-        // Calculate radius: R = (L²/4 + e²) / (2|e|)
-        let radius = (chord_len * chord_len / 4.0 + e * e) / (2.0 * e.abs());
+        check_grid_lines(grid, self.config.max_array_instances)?;
+
+        self.write_line(&format!(
+            "<g id=\"{}\"{}>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element)
+        ));
+        self.indent += 1;
+
+        for row in 0..=grid.rows {
+            let y = y0 + f64::from(row) * self.scaled(grid.cell_height);
+            self.write_line(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+                x0, y, x1, y, style
+            ));
+        }
 
-        // Large arc flag: if |r| > 0.5, arc is > 180 degrees
-        let large_arc = if r.abs() > 0.5 { 1 } else { 0 };
+        for col in 0..=grid.columns {
+            let x = x0 + f64::from(col) * self.scaled(grid.cell_width);
+            self.write_line(&format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+                x, y0, x, y1, style
+            ));
+        }
 
-        // Sweep flag: positive offset means curve bulges to the left of direction
-        // In SVG: sweep=1 means clockwise
-        let sweep = if offset > 0 { 1 } else { 0 };
+        self.indent -= 1;
+        self.write_line("</g>");
 
-        format!(
-            "A {:.2} {:.2} 0 {} {} {} {}",
-            radius, radius, large_arc, sweep, x2, y2
-        )
+        Ok(())
     }
 
     /// Writes a simple shape element.
@@ -373,18 +793,43 @@ impl<'a> SvgContext<'a> {
 
         let style = self.build_style(&ss.attributes);
 
-        // Since simple shape parsing is incomplete, we just output a placeholder
+        let (cx, cy) = ss.center();
+        let (cx, cy) = (self.snap(cx * self.scale), self.snap(cy * self.scale));
+
+        // SVG shapes cannot rotate themselves, so rotate about their center
+        let rotation = ss
+            .rotation
+            .map(|r| {
+                let degrees = f64::from(r) * self.angle_resolution;
+                format!(" transform=\"rotate({} {} {})\"", degrees, cx, cy)
+            })
+            .unwrap_or_default();
+
         match ss.shape_type {
             SimpleShapeType::Rectangle => {
                 self.write_line(&format!(
-                    "<rect id=\"{}\" x=\"0\" y=\"0\" width=\"10\" height=\"10\" {}/>",
-                    element.id, style
+                    "<rect id=\"{}\"{} x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{} {}/>",
+                    self.prefixed_id(&element.id),
+                    self.data_attributes(element),
+                    self.scaled(ss.origin.x),
+                    self.scaled(ss.origin.y),
+                    self.scaled(ss.width),
+                    self.scaled(ss.height),
+                    rotation,
+                    style
                 ));
             }
             SimpleShapeType::Ellipse => {
                 self.write_line(&format!(
-                    "<ellipse id=\"{}\" cx=\"5\" cy=\"5\" rx=\"5\" ry=\"5\" {}/>",
-                    element.id, style
+                    "<ellipse id=\"{}\"{} cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{} {}/>",
+                    self.prefixed_id(&element.id),
+                    self.data_attributes(element),
+                    cx,
+                    cy,
+                    self.snap(f64::from(ss.width) / 2.0 * self.scale),
+                    self.snap(f64::from(ss.height) / 2.0 * self.scale),
+                    rotation,
+                    style
                 ));
             }
         }
@@ -399,8 +844,23 @@ impl<'a> SvgContext<'a> {
             element.id, reuse.element_index
         );
 
+        // A <use> can only reliably reference an element defined before it
+        if reuse.element_index as usize >= self.element_index {
+            return Err(WvgError::InvalidReuse {
+                index: reuse.element_index,
+                position: self.element_index,
+            });
+        }
+
         // Find the referenced element
-        let ref_id = format!("el_{}", reuse.element_index);
+        let ref_id = self
+            .document
+            .resolve_reuse(reuse)
+            .map(|target| self.prefixed_id(&target.id))
+            .ok_or(WvgError::ElementIndexOutOfBounds {
+                index: reuse.element_index,
+                max: self.document.elements.len().saturating_sub(1),
+            })?;
         let transform_str = self.build_transform(&reuse.transform);
 
         // Handle array parameters
@@ -415,14 +875,43 @@ impl<'a> SvgContext<'a> {
                 .unwrap_or_default();
 
             self.write_line(&format!(
-                "<use id=\"{}\" href=\"#{}\" {} {}/>",
-                element.id, ref_id, transform_str, style
+                "<use id=\"{}\"{} {}=\"#{}\" {} {}/>",
+                self.prefixed_id(&element.id),
+                self.data_attributes(element),
+                self.href(),
+                ref_id,
+                transform_str,
+                style
             ));
         }
 
         Ok(())
     }
 
+    /// Returns `id` with the configured prefix.
+    fn prefixed_id(&self, id: &str) -> String {
+        format!("{}{}", self.config.id_prefix, id)
+    }
+
+    /// Returns the ` id` attribute of an array instance of `element`, or
+    /// nothing if array ids are omitted.
+    fn instance_id(&self, element: &WvgElement, suffix: &str) -> String {
+        if self.config.omit_array_ids {
+            String::new()
+        } else {
+            format!(" id=\"{}_{}\"", self.prefixed_id(&element.id), suffix)
+        }
+    }
+
+    /// Returns the attribute name used for `<use>` references.
+    fn href(&self) -> &'static str {
+        if self.config.xlink_compat {
+            "xlink:href"
+        } else {
+            "href"
+        }
+    }
+
     /// Writes an array of reuse elements.
     fn write_array_reuse(
         &mut self,
@@ -437,6 +926,15 @@ impl<'a> SvgContext<'a> {
             array.columns, array.rows
         );
 
+        let instances = usize::from(array.columns) * usize::from(array.rows);
+        if instances > self.config.max_array_instances {
+            return Err(WvgError::LimitExceeded {
+                what: "array instance count",
+                value: instances,
+                limit: self.config.max_array_instances,
+            });
+        }
+
         let width = array.width.unwrap_or(0);
         let height = array.height.unwrap_or(width);
         let style = reuse
@@ -445,21 +943,34 @@ impl<'a> SvgContext<'a> {
             .map(|a| self.build_style(a))
             .unwrap_or_default();
 
+        if self.config.array_as_nested_use {
+            return self.write_nested_array_reuse(element, ref_id, array, base_transform, &style);
+        }
+
         let mut instance_idx = 0;
         for row in 0..array.rows {
             for col in 0..array.columns {
-                let tx = i32::from(col) * width;
-                let ty = i32::from(row) * height;
-
-                let combined_transform = if tx != 0 || ty != 0 {
-                    format!("{} translate({}, {})", base_transform, tx, ty)
+                let tx = i32::from(col).checked_mul(width).ok_or(WvgError::CoordinateOverflow)?;
+                let ty = i32::from(row).checked_mul(height).ok_or(WvgError::CoordinateOverflow)?;
+                let (tx, ty) = (self.scaled(tx), self.scaled(ty));
+
+                // The cell offset applies first, so it comes last in the list
+                let combined_transform = if tx != 0.0 || ty != 0.0 {
+                    let mut parts = self.transform_list(&reuse.transform);
+                    parts.push(format!("translate({}, {})", tx, ty));
+                    format!("transform=\"{}\"", parts.join(" "))
                 } else {
                     base_transform.to_string()
                 };
 
                 self.write_line(&format!(
-                    "<use id=\"{}_{}_{}\" href=\"#{}\" {} {}/>",
-                    element.id, row, col, ref_id, combined_transform.trim(), style
+                    "<use{}{} {}=\"#{}\" {} {}/>",
+                    self.instance_id(element, &format!("{}_{}", row, col)),
+                    self.data_attributes(element),
+                    self.href(),
+                    ref_id,
+                    combined_transform.trim(),
+                    style
                 ));
 
                 instance_idx += 1;
@@ -470,6 +981,73 @@ impl<'a> SvgContext<'a> {
         Ok(())
     }
 
+    /// Writes an array of reuse elements by building the first row once and
+    /// reusing that row for every further row.
+    fn write_nested_array_reuse(
+        &mut self,
+        element: &WvgElement,
+        ref_id: &str,
+        array: &ArrayParams,
+        base_transform: &str,
+        style: &str,
+    ) -> WvgResult<()> {
+        let width = array.width.unwrap_or(0);
+        let height = array.height.unwrap_or(width);
+        let row_id = format!("{}_row", self.prefixed_id(&element.id));
+
+        self.write_line(&format!(
+            "<g id=\"{}\"{} {} {}>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element),
+            base_transform,
+            style
+        ));
+        self.indent += 1;
+
+        self.write_line(&format!("<g id=\"{}\">", row_id));
+        self.indent += 1;
+        for col in 0..array.columns {
+            let tx = i32::from(col).checked_mul(width).ok_or(WvgError::CoordinateOverflow)?;
+            let tx = self.scaled(tx);
+            let transform = if tx != 0.0 {
+                format!("transform=\"translate({}, 0)\" ", tx)
+            } else {
+                String::new()
+            };
+            self.write_line(&format!(
+                "<use{} {}=\"#{}\" {}/>",
+                self.instance_id(element, &format!("0_{}", col)),
+                self.href(),
+                ref_id,
+                transform
+            ));
+        }
+        self.indent -= 1;
+        self.write_line("</g>");
+
+        for row in 1..array.rows {
+            let ty = i32::from(row).checked_mul(height).ok_or(WvgError::CoordinateOverflow)?;
+            let ty = self.scaled(ty);
+            self.write_line(&format!(
+                "<use{} {}=\"#{}\" transform=\"translate(0, {})\" />",
+                self.instance_id(element, &row.to_string()),
+                self.href(),
+                row_id,
+                ty
+            ));
+        }
+
+        self.indent -= 1;
+        self.write_line("</g>");
+
+        trace!(
+            "Wrote {} array instances as {} row references",
+            array.columns,
+            array.rows
+        );
+        Ok(())
+    }
+
     /// Writes a group start element.
     fn write_group_start(
         &mut self,
@@ -484,15 +1062,38 @@ impl<'a> SvgContext<'a> {
             .map(|t| self.build_transform(t))
             .unwrap_or_default();
 
-        let display = if gs.display { "" } else { " display=\"none\"" };
+        let display = match (gs.display, self.config.hidden_mode) {
+            (true, _) => "",
+            (false, HiddenMode::Display) => " display=\"none\"",
+            (false, HiddenMode::Visibility) => " visibility=\"hidden\"",
+        };
+
+        let opacity = gs
+            .opacity
+            .map(|o| format!(" opacity=\"{}\"", o.clamp(0.0, 1.0)))
+            .unwrap_or_default();
+
+        self.group_count += 1;
+        let aria = if self.config.emit_aria {
+            format!(" role=\"group\" aria-label=\"Group {}\"", self.group_count)
+        } else {
+            String::new()
+        };
 
         self.write_line(&format!(
-            "<g id=\"{}\" {}{}>",
-            element.id, transform_str, display
+            "<g id=\"{}\"{} {}{}{}{}>",
+            self.prefixed_id(&element.id),
+            self.data_attributes(element),
+            transform_str,
+            display,
+            opacity,
+            aria
         ));
 
         self.indent += 1;
-        self.group_stack.push(true);
+        let hidden = !gs.display || self.in_hidden_group();
+        let inherited = gs.attributes.or(&self.inherited_attributes());
+        self.group_stack.push((hidden, inherited));
 
         Ok(())
     }
@@ -501,7 +1102,9 @@ impl<'a> SvgContext<'a> {
     fn write_group_end(&mut self) -> WvgResult<()> {
         debug!("Writing group end");
 
-        if self.group_stack.pop().is_some() {
+        if !self.group_stack.is_empty() {
+            self.close_envelopes();
+            self.group_stack.pop();
             self.indent -= 1;
             self.write_line("</g>");
         }
@@ -509,24 +1112,124 @@ impl<'a> SvgContext<'a> {
         Ok(())
     }
 
-    /// Builds a transform string from transform data.
-    fn build_transform(&self, t: &Transform) -> String {
-        let mut parts = Vec::new();
+    /// Writes a local envelope as a `<clipPath>` of its bounds and opens a
+    /// group clipped to it, which holds the following elements up to the
+    /// end of the enclosing group.
+    fn write_local_envelope(
+        &mut self,
+        element: &WvgElement,
+        envelope: &LocalEnvelopeElement,
+    ) -> WvgResult<()> {
+        debug!(
+            "Writing local envelope {}: {}x{}",
+            element.id, envelope.width, envelope.height
+        );
 
-        // Translate
-        let tx = t.translate_x.unwrap_or(0);
-        let ty = t.translate_y.unwrap_or(0);
-        if tx != 0 || ty != 0 {
-            parts.push(format!("translate({}, {})", tx, ty));
-        }
+        let id = self.prefixed_id(&element.id);
+        let clip_id = format!("{}_clip", id);
+        self.write_line(&format!("<clipPath id=\"{}\">", clip_id));
+        self.indent += 1;
+        self.write_line(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+            self.scaled(envelope.origin.x),
+            self.scaled(envelope.origin.y),
+            self.scaled(envelope.width.max(0)),
+            self.scaled(envelope.height.max(0))
+        ));
+        self.indent -= 1;
+        self.write_line("</clipPath>");
 
-        // Rotation (around center if specified)
-        if let Some(angle_val) = t.angle {
-            let degrees = angle_val as f64 * self.angle_resolution;
-            let cx = t.cx.unwrap_or(0);
-            let cy = t.cy.unwrap_or(0);
-            if cx != 0 || cy != 0 {
-                parts.push(format!("rotate({} {} {})", degrees, cx, cy));
+        self.write_line(&format!(
+            "<g id=\"{}\"{} clip-path=\"url(#{})\">",
+            id,
+            self.data_attributes(element),
+            clip_id
+        ));
+        self.indent += 1;
+        self.envelope_stack.push(self.group_stack.len());
+
+        Ok(())
+    }
+
+    /// Closes the groups of local envelopes started at the current group
+    /// depth.
+    fn close_envelopes(&mut self) {
+        while self.envelope_stack.last() == Some(&self.group_stack.len()) {
+            self.envelope_stack.pop();
+            self.indent -= 1;
+            self.write_line("</g>");
+        }
+    }
+
+    /// Returns whether the element being written is inside a hidden group.
+    fn in_hidden_group(&self) -> bool {
+        self.group_stack.last().is_some_and(|(hidden, _)| *hidden)
+    }
+
+    /// Returns the attributes inherited from the enclosing groups.
+    fn inherited_attributes(&self) -> ElementAttributes {
+        self.group_stack.last().map(|(_, attrs)| attrs.clone()).unwrap_or_default()
+    }
+
+    /// Builds the `data-wvg-*` attributes identifying the source element.
+    ///
+    /// Returns an empty string unless enabled in the configuration; otherwise
+    /// the result starts with a space so it can follow the `id` attribute.
+    fn data_attributes(&self, element: &WvgElement) -> String {
+        if !self.config.emit_data_attributes {
+            return String::new();
+        }
+
+        let element_type = match element.data {
+            ElementData::Polyline(_) => "polyline",
+            ElementData::CircularPolyline(_) => "circular_polyline",
+            ElementData::SimpleShape(_) => "simple_shape",
+            ElementData::SpecialShape(_) => "special_shape",
+            ElementData::Reuse(_) => "reuse",
+            ElementData::GroupStart(_) | ElementData::GroupEnd => "group",
+            ElementData::LocalEnvelope(_) => "local_envelope",
+        };
+
+        format!(
+            " data-wvg-type=\"{}\" data-wvg-index=\"{}\"",
+            element_type, self.element_index
+        )
+    }
+
+    /// Builds a transform attribute from transform data, or an empty string
+    /// for the identity.
+    fn build_transform(&self, t: &Transform) -> String {
+        let parts = self.transform_list(t);
+        if parts.is_empty() {
+            String::new()
+        } else {
+            format!("transform=\"{}\"", parts.join(" "))
+        }
+    }
+
+    /// Returns the SVG transform functions for transform data.
+    fn transform_list(&self, t: &Transform) -> Vec<String> {
+        let mut parts = Vec::new();
+
+        // Translate
+        let tx = t.translate_x.unwrap_or(0);
+        let ty = t.translate_y.unwrap_or(0);
+        if tx != 0 || ty != 0 {
+            parts.push(format!("translate({}, {})", self.scaled(tx), self.scaled(ty)));
+        }
+
+        // Rotation (around center if specified)
+        if let Some(angle_val) = t.angle {
+            let degrees = angle_val as f64 * self.angle_resolution;
+            let cx = t.cx.unwrap_or(0);
+            let cy = t.cy.unwrap_or(0);
+            if cx != 0 || cy != 0 {
+                parts.push(format!(
+                    "rotate({} {} {})",
+                    degrees,
+                    self.scaled(cx),
+                    self.scaled(cy)
+                ));
             } else {
                 parts.push(format!("rotate({})", degrees));
             }
@@ -546,15 +1249,36 @@ impl<'a> SvgContext<'a> {
             _ => {}
         }
 
-        if parts.is_empty() {
+        parts
+    }
+
+    /// Builds a style string from element attributes.
+    ///
+    /// Returns a `class` reference instead of an inline `style` attribute
+    /// when CSS classes are enabled.
+    fn build_style(&self, attrs: &ElementAttributes) -> String {
+        // Nothing inside a group removed from layout is rendered, so skip its
+        // styling. Invisible groups still take up space, so keep theirs.
+        if self.config.hidden_mode == HiddenMode::Display && self.in_hidden_group() {
+            return String::new();
+        }
+
+        // The stylesheet sets the shape defaults on the elements themselves,
+        // so group attributes are resolved here rather than left to the
+        // SVG cascade
+        let declarations = self.style_declarations(&attrs.or(&self.inherited_attributes()));
+
+        if declarations.is_empty() {
             String::new()
+        } else if let Some(index) = self.style_classes.get(&declarations) {
+            format!("class=\"s{}\"", index + 1)
         } else {
-            format!("transform=\"{}\"", parts.join(" "))
+            format!("style=\"{}\"", declarations)
         }
     }
 
-    /// Builds a style string from element attributes.
-    fn build_style(&self, attrs: &ElementAttributes) -> String {
+    /// Builds the CSS declarations for element attributes.
+    fn style_declarations(&self, attrs: &ElementAttributes) -> String {
         let mut styles = Vec::new();
 
         // Line type (stroke-dasharray)
@@ -570,42 +1294,94 @@ impl<'a> SvgContext<'a> {
             }
         }
 
-        // Line width
+        // Line width; a zero width means the element has no outline at all
+        let no_line = attrs.line_width == Some(LineWidth::None);
         if let Some(line_width) = attrs.line_width {
-            let scale = self.config.line_width_scale.unwrap_or(1.0);
+            let scale = self.config.line_width_scale.unwrap_or(1.0) * self.scale as f32;
             let width = match line_width {
-                LineWidth::None => 0.0,
-                LineWidth::Fine => 1.0 * scale,
-                LineWidth::Normal => 2.0 * scale,
-                LineWidth::Thick => 3.0 * scale,
+                LineWidth::None => None,
+                LineWidth::Fine => Some(1.0 * scale),
+                LineWidth::Normal => Some(2.0 * scale),
+                LineWidth::Thick => Some(3.0 * scale),
             };
-            styles.push(format!("stroke-width: {}", width));
+            match width {
+                Some(width) => styles.push(format!("stroke-width: {}", width)),
+                None => styles.push("stroke: none".to_string()),
+            }
         }
 
         // Line color
-        if let Some(ref color) = attrs.line_color {
-            styles.push(format!("stroke: {}", color_to_hex(color)));
+        if let Some(ref color) = attrs.line_color.filter(|_| !no_line) {
+            styles.push(format!("stroke: {}", self.hex(color)));
+            if !color.is_opaque() {
+                styles.push(format!("stroke-opacity: {}", alpha_to_opacity(color.a)));
+            }
         }
 
         // Fill
         if let Some(has_fill) = attrs.fill {
             if has_fill {
-                if let Some(ref fill_color) = attrs.fill_color {
-                    styles.push(format!("fill: {}", color_to_hex(fill_color)));
+                // The stylesheet default is `none` without a default fill
+                // color, so a filled element always names its color
+                let fill_color = self.document.header.color_config.resolve(attrs).fill_color;
+                if let Some(ref fill_color) = fill_color {
+                    styles.push(format!("fill: {}", self.hex(fill_color)));
+                    if !fill_color.is_opaque() {
+                        styles.push(format!("fill-opacity: {}", alpha_to_opacity(fill_color.a)));
+                    }
+                } else {
+                    styles.push("fill: currentColor".to_string());
                 }
-                // Otherwise use default fill
+
+                let rule = match self.config.fill_rule {
+                    FillRule::NonZero => "nonzero",
+                    FillRule::EvenOdd => "evenodd",
+                };
+                styles.push(format!("fill-rule: {}", rule));
             } else {
                 styles.push("fill: none".to_string());
             }
         }
 
-        if styles.is_empty() {
-            String::new()
+        if self.config.minify {
+            styles.join(";").replace(": ", ":")
         } else {
-            format!("style=\"{}\"", styles.join("; "))
+            styles.join("; ")
         }
     }
 
+    /// Assigns a CSS class to each distinct style used by the document,
+    /// numbered in order of first appearance.
+    fn collect_style_classes(&mut self) {
+        let mut classes: Vec<String> = Vec::new();
+        let mut inherited = vec![ElementAttributes::default()];
+
+        for element in &self.document.elements {
+            match &element.data {
+                ElementData::GroupStart(gs) => {
+                    inherited.push(gs.attributes.or(inherited.last().unwrap()));
+                }
+                ElementData::GroupEnd if inherited.len() > 1 => {
+                    inherited.pop();
+                }
+                _ => {}
+            }
+            if let Some(attrs) = element.data.attributes() {
+                let declarations = self.style_declarations(&attrs.or(inherited.last().unwrap()));
+                if !declarations.is_empty() && !classes.contains(&declarations) {
+                    classes.push(declarations);
+                }
+            }
+        }
+
+        debug!("Collected {} style classes", classes.len());
+        self.style_classes = classes
+            .into_iter()
+            .enumerate()
+            .map(|(i, declarations)| (declarations, i))
+            .collect();
+    }
+
     /// Writes the SVG footer.
     fn write_footer(&mut self) {
         self.indent -= 1;
@@ -613,8 +1389,1267 @@ impl<'a> SvgContext<'a> {
     }
 }
 
+/// Largest arc radius drawn as an arc, as a multiple of the drawing size.
+pub(crate) const MAX_RADIUS_FACTOR: f64 = 100.0;
+
+/// Returns the signed displacement `e` of the arc midpoint from the chord
+/// midpoint and the arc radius for a curve offset over a chord of length
+/// `chord_len`.
+///
+/// The offset is a fraction of the chord length with denominator
+/// `2^n - 2`, where `n` is 4 or 5 bits depending on the curve offset width.
+pub(crate) fn arc_geometry(
+    chord_len: f64,
+    offset: i32,
+    curve_offset_in_bits: Option<u8>,
+) -> (f64, f64) {
+    let n = if curve_offset_in_bits.unwrap_or(0) == 1 { 5 } else { 4 };
+    let k = ((1 << n) - 2) as f64;
+    let e = offset as f64 / k * chord_len;
+
+    // R = (L²/4 + e²) / (2|e|)
+    let radius = (chord_len * chord_len / 4.0 + e * e) / (2.0 * e.abs());
+    (e, radius)
+}
+
+/// Derives the SVG large-arc and sweep flags for an arc spanning the chord
+/// `(dx, dy)` whose midpoint is displaced from the chord midpoint by the signed
+/// curve offset `e`.
+///
+/// The arc midpoint lies on the chord normal `(dy, -dx) / L`, so a positive
+/// offset bulges to the left of the direction of travel as seen on screen
+/// (y axis pointing down). SVG traces an arc with `sweep = 1` in the direction
+/// of increasing angle, which is clockwise on screen; this is the case exactly
+/// when the arc midpoint lies to the left of the chord, i.e. when the cross
+/// product of the chord and the vector to the arc midpoint is negative.
+///
+/// The arc spans more than 180 degrees when the offset exceeds the radius,
+/// which reduces to `|e| > L / 2`.
+///
+/// Since the offset is relative to the direction of travel, the sweep flag
+/// depends on its sign alone. Mirroring the geometry therefore requires
+/// negating the offsets as well; see `CircularPolylineElement::mirrored_x`.
+pub(crate) fn arc_flags(dx: f64, dy: f64, e: f64) -> (u8, u8) {
+    let chord_len = (dx * dx + dy * dy).sqrt();
+
+    // Arc midpoint relative to the start point
+    let mid_x = dx / 2.0 + e * dy / chord_len;
+    let mid_y = dy / 2.0 - e * dx / chord_len;
+
+    let cross = dx * mid_y - dy * mid_x;
+    let sweep = if cross < 0.0 { 1 } else { 0 };
+    let large_arc = if e.abs() > chord_len / 2.0 { 1 } else { 0 };
+
+    (large_arc, sweep)
+}
+
+/// Returns the smallest and largest x/y used by the document geometry, with
+/// the minimum capped at 0 and the maximum floored at 0.
+///
+/// Only polylines, circular polylines, simple shapes and grids are
+/// considered; transforms applied by reuse elements and groups are ignored.
+fn content_bounds(document: &WvgDocument) -> ((i32, i32), (i32, i32)) {
+    let mut min = (0i32, 0i32);
+    let mut max = (0i32, 0i32);
+    let mut include = |x: i32, y: i32| {
+        min.0 = min.0.min(x);
+        min.1 = min.1.min(y);
+        max.0 = max.0.max(x);
+        max.1 = max.1.max(y);
+    };
+
+    for element in &document.elements {
+        match &element.data {
+            ElementData::Polyline(pl) => {
+                for p in &pl.points {
+                    include(p.x, p.y);
+                }
+            }
+            ElementData::CircularPolyline(cp) => {
+                let mut current = (0i32, 0i32);
+                for pt in &cp.points {
+                    current = if pt.is_absolute {
+                        (pt.point.x, pt.point.y)
+                    } else {
+                        (
+                            current.0.saturating_add(pt.point.x),
+                            current.1.saturating_add(pt.point.y),
+                        )
+                    };
+                    include(current.0, current.1);
+                }
+            }
+            ElementData::SimpleShape(ss) => {
+                include(ss.origin.x, ss.origin.y);
+                include(
+                    ss.origin.x.saturating_add(ss.width),
+                    ss.origin.y.saturating_add(ss.height),
+                );
+            }
+            ElementData::SpecialShape(shape) => match &shape.shape {
+                SpecialShape::Grid(grid) => {
+                    let columns = i32::try_from(grid.columns).unwrap_or(i32::MAX);
+                    let rows = i32::try_from(grid.rows).unwrap_or(i32::MAX);
+                    include(grid.origin.x, grid.origin.y);
+                    include(
+                        grid.origin.x.saturating_add(columns.saturating_mul(grid.cell_width)),
+                        grid.origin.y.saturating_add(rows.saturating_mul(grid.cell_height)),
+                    );
+                }
+                SpecialShape::Star(star) => {
+                    let radius = star.outer_radius.saturating_abs();
+                    let radius = radius.max(star.inner_radius.saturating_abs());
+                    let Point { x, y } = star.center;
+                    include(x.saturating_sub(radius), y.saturating_sub(radius));
+                    include(x.saturating_add(radius), y.saturating_add(radius));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    (min, max)
+}
+
+/// Percent-encodes everything but unreserved characters and URI delimiters
+/// that are harmless inside a quoted attribute.
+///
+/// `'` and `&` are escaped too, so the result can be placed in single-quoted
+/// attributes and in HTML without further escaping.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' => encoded.push(char::from(byte)),
+            b'-' | b'.' | b'_' | b'~' | b'!' | b'$' | b'(' | b')' | b'*' => {
+                encoded.push(char::from(byte))
+            }
+            b'+' | b',' | b';' | b'=' | b':' | b'@' | b'/' | b'?' => encoded.push(char::from(byte)),
+            _ => write!(&mut encoded, "%{:02X}", byte).unwrap(),
+        }
+    }
+    encoded
+}
+
+/// Encodes bytes as padded standard base64.
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
 /// Converts a `Color` to a hex string.
-fn color_to_hex(color: &Color) -> String {
+///
+/// The alpha channel is not included; it is emitted separately as an
+/// opacity property so the output stays compatible with SVG 1.1.
+pub(crate) fn color_to_hex(color: &Color) -> String {
     format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
 }
 
+/// Converts a `Color` to the shortest hex string, `#rgb` when every channel
+/// has two identical nibbles.
+fn color_to_short_hex(color: &Color) -> String {
+    let collapsible = |c: u8| c >> 4 == c & 0x0f;
+    if collapsible(color.r) && collapsible(color.g) && collapsible(color.b) {
+        format!("#{:x}{:x}{:x}", color.r & 0x0f, color.g & 0x0f, color.b & 0x0f)
+    } else {
+        color_to_hex(color)
+    }
+}
+
+/// Removes whitespace from path data that is not needed to separate numbers.
+///
+/// Spaces around command letters are dropped, as are spaces before a minus
+/// sign, so `M 10 20 l 3 -4` becomes `M10 20l3-4`.
+fn minify_path_data(d: &str) -> String {
+    let mut out = String::with_capacity(d.len());
+    let mut prev_was_number = false;
+
+    for token in d.split_whitespace() {
+        let is_command = token.chars().all(|c| c.is_ascii_alphabetic());
+        if prev_was_number && !is_command && !token.starts_with('-') {
+            out.push(' ');
+        }
+        out.push_str(token);
+        prev_was_number = !is_command;
+    }
+
+    out
+}
+
+/// Breaks path data onto new lines so no line exceeds `column` characters,
+/// splitting only between commands. A single command longer than `column`
+/// gets a line of its own.
+fn wrap_path_data(d: &str, column: usize) -> String {
+    let mut commands: Vec<String> = Vec::new();
+    for token in d.split_whitespace() {
+        match commands.last_mut() {
+            Some(command) if !token.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                command.push(' ');
+                command.push_str(token);
+            }
+            _ => commands.push(token.to_string()),
+        }
+    }
+
+    let mut out = String::with_capacity(d.len());
+    let mut line_len = 0;
+    for command in commands {
+        if line_len > 0 && line_len + 1 + command.len() > column {
+            out.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            out.push(' ');
+            line_len += 1;
+        }
+        line_len += command.len();
+        out.push_str(&command);
+    }
+
+    out
+}
+
+/// Converts an alpha value to an SVG opacity with at most three decimals.
+fn alpha_to_opacity(alpha: u8) -> String {
+    let opacity = format!("{:.3}", f64::from(alpha) / 255.0);
+    opacity
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal 128x32 flat document with the given elements.
+    fn document(elements: Vec<WvgElement>) -> WvgDocument {
+        WvgDocument {
+            header: WvgHeader {
+                general_info: GeneralInfo::default(),
+                color_config: ColorConfig::default(),
+                codec_params: CodecParams {
+                    element_masks: vec![false; 8],
+                    attribute_masks: AttributeMasks::default(),
+                    generic_params: GenericParams::default(),
+                    coord_params: CoordinateParams::Flat(FlatCoordinateParams {
+                        drawing_width: 128,
+                        drawing_height: 32,
+                        max_x_in_bits: 7,
+                        max_y_in_bits: 5,
+                        xy_all_positive: true,
+                        trans_xy_in_bits: 7,
+                        num_points_in_bits: 4,
+                        offset_x_in_bits_level1: 3,
+                        offset_y_in_bits_level1: 3,
+                        offset_x_in_bits_level2: 5,
+                        offset_y_in_bits_level2: 5,
+                    }),
+                },
+                animation_mode: None,
+            },
+            elements,
+        }
+    }
+
+    fn polyline(id: &str, points: &[(i32, i32)]) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::Polyline(PolylineElement {
+                attributes: ElementAttributes::default(),
+                points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            }),
+        }
+    }
+
+    fn array_reuse(id: &str, element_index: u32, columns: u8, rows: u8) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::Reuse(ReuseElement {
+                element_index,
+                transform: Transform::default(),
+                array_params: Some(ArrayParams {
+                    columns,
+                    rows,
+                    width: Some(8),
+                    height: Some(4),
+                }),
+                override_attributes: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_xlink_compat() {
+        let doc = document(vec![polyline("el_0", &[(1, 1)]), array_reuse("el_1", 0, 2, 1)]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(!svg.contains("xlink"));
+        assert!(svg.contains(r##"<use id="el_1_0_0" href="#el_0""##));
+
+        let config = ConverterConfig::new().with_xlink_compat(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" viewBox"#
+        ));
+        assert!(svg.contains(r##"<use id="el_1_0_0" xlink:href="#el_0""##));
+        assert!(!svg.contains(" href="));
+    }
+
+    #[test]
+    fn test_array_ids_use_prefix() {
+        let doc = document(vec![polyline("el_0", &[(1, 1)]), array_reuse("el_1", 0, 2, 2)]);
+
+        let config = ConverterConfig::new().with_id_prefix("doc1-");
+        let svg = SvgConverter::with_config(config.clone()).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<circle id="doc1-el_0""#));
+        assert!(svg.contains(r##"<use id="doc1-el_1_0_0" href="#doc1-el_0""##));
+        assert!(svg.contains(r##"<use id="doc1-el_1_1_1" href="#doc1-el_0" transform="translate(8, 4)" />"##));
+
+        let nested = config.clone().with_array_as_nested_use(true);
+        let svg = SvgConverter::with_config(nested).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<g id="doc1-el_1_row">"#));
+        assert!(svg.contains(r##"<use id="doc1-el_1_1" href="#doc1-el_1_row""##));
+
+        let svg = SvgConverter::with_config(config.with_omit_array_ids(true)).convert(&doc).unwrap();
+        assert!(svg.contains(r##"<use href="#doc1-el_0" transform="translate(8, 4)" />"##));
+        assert!(!svg.contains("el_1_"));
+    }
+
+    #[test]
+    fn test_max_array_instances() {
+        let doc = document(vec![polyline("el_0", &[(1, 1)]), array_reuse("el_1", 0, 100, 100)]);
+
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(
+            result,
+            Err(WvgError::LimitExceeded { what: "array instance count", value: 10000, limit: 4096 })
+        ));
+
+        let config = ConverterConfig::new().with_array_as_nested_use(true);
+        assert!(SvgConverter::with_config(config).convert(&doc).is_err());
+
+        let config = ConverterConfig::new().with_max_array_instances(10000);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert_eq!(svg.matches("<use ").count(), 10000);
+    }
+
+    #[test]
+    fn test_forward_reuse_rejected() {
+        let doc = document(vec![array_reuse("el_0", 1, 1, 1), polyline("el_1", &[(1, 1)])]);
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(result, Err(WvgError::InvalidReuse { index: 1, position: 0 })));
+    }
+
+    #[test]
+    fn test_negative_origin() {
+        let mut doc = document(vec![polyline("el_0", &[(-4, -2), (10, 6)])]);
+        if let CoordinateParams::Flat(params) = &mut doc.header.codec_params.coord_params {
+            params.xy_all_positive = false;
+        }
+        doc.header.color_config.background_color = Some(Color::WHITE);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"viewBox="-4 -2 132 34""#));
+        assert!(svg.contains(r##"<rect x="-4" y="-2" width="132" height="34" fill="#ffffff"/>"##));
+        assert!(svg.contains(r#"d="M -4 -2 l 14 8""#));
+    }
+
+    #[test]
+    fn test_zero_dimension_uses_content_extent() {
+        let mut doc = document(vec![polyline("el_0", &[(4, 2), (40, 18)])]);
+        if let CoordinateParams::Flat(params) = &mut doc.header.codec_params.coord_params {
+            params.drawing_width = 0;
+        }
+
+        let (svg, warnings) = SvgConverter::new().convert_with_warnings(&doc).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 40 32""#));
+        assert_eq!(warnings, vec![ParseWarning::ZeroDimension]);
+    }
+
+    #[test]
+    fn test_positive_origin_unchanged() {
+        let mut doc = document(vec![polyline("el_0", &[(4, 2), (10, 6)])]);
+        if let CoordinateParams::Flat(params) = &mut doc.header.codec_params.coord_params {
+            params.xy_all_positive = false;
+        }
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 128 32""#));
+    }
+
+    #[test]
+    fn test_padding() {
+        let mut doc = document(vec![polyline("el_0", &[(0, 0), (10, 10)])]);
+        if let CoordinateParams::Flat(params) = &mut doc.header.codec_params.coord_params {
+            params.drawing_width = 10;
+            params.drawing_height = 10;
+        }
+
+        let config = ConverterConfig::new().with_padding(2.0);
+        let svg = SvgConverter::with_config(config.clone()).convert(&doc).unwrap();
+        assert!(svg.contains(r#"viewBox="-2 -2 14 14""#));
+
+        let svg = SvgConverter::with_config(config.with_max_dimension(7)).convert(&doc).unwrap();
+        assert!(svg.contains(r#"width="7" height="7" viewBox="-2 -2 14 14""#));
+    }
+
+    #[test]
+    fn test_max_dimension() {
+        let doc = document(vec![polyline("el_0", &[(1, 1)])]);
+        let config = ConverterConfig::new().with_max_dimension(64);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"width="64" height="16" viewBox="0 0 128 32""#));
+    }
+
+    #[test]
+    fn test_clamp_to_bounds() {
+        let doc = document(vec![polyline("el_0", &[(10, 5), (200, 5), (200, -3)])]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 10 5 l 190 0 l 0 -8""#));
+
+        let config = ConverterConfig::new().with_clamp_to_bounds(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 10 5 l 118 0 l 0 -5""#));
+    }
+
+    fn group_start(id: &str, display: bool, opacity: Option<f32>) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::GroupStart(GroupStartElement {
+                transform: None,
+                display,
+                opacity,
+                attributes: ElementAttributes::default(),
+            }),
+        }
+    }
+
+    fn group_end(id: &str) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::GroupEnd,
+        }
+    }
+
+    #[test]
+    fn test_hidden_group_children() {
+        let doc = document(vec![
+            group_start("el_0", false, None),
+            group_start("el_1", true, None),
+            styled_polyline("el_2", LineWidth::Thick),
+            group_end("el_3"),
+            group_end("el_4"),
+            styled_polyline("el_5", LineWidth::Thick),
+        ]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"<g id="el_0"  display="none"><g id="el_1" >"#));
+        assert!(svg.contains(r#"<path id="el_2" d="M 0 0 l 4 4" /></g></g>"#));
+        assert!(svg.contains(r#"<path id="el_5" d="M 0 0 l 4 4" style="stroke-width: 3"/>"#));
+    }
+
+    #[test]
+    fn test_output_scale_scales_stroke_widths() {
+        let doc = document(vec![styled_polyline("el_0", LineWidth::Thick)]);
+        let config = ConverterConfig::new().with_output_scale(2.0);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<path id="el_0" d="M 0 0 l 8 8" style="stroke-width: 6"/>"#));
+        assert!(svg.contains("stroke-width: 2; }</style>"));
+    }
+
+    #[test]
+    fn test_hidden_group_visibility_mode() {
+        let doc = document(vec![
+            group_start("el_0", false, None),
+            styled_polyline("el_1", LineWidth::Thick),
+            group_end("el_2"),
+        ]);
+
+        let config = ConverterConfig::new().with_hidden_mode(HiddenMode::Visibility);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<g id="el_0"  visibility="hidden">"#));
+        assert!(!svg.contains("display="));
+        assert!(svg.contains(r#"<path id="el_1" d="M 0 0 l 4 4" style="stroke-width: 3"/>"#));
+    }
+
+    #[test]
+    fn test_hidden_group_display_mode() {
+        let doc = document(vec![
+            group_start("el_0", false, None),
+            styled_polyline("el_1", LineWidth::Thick),
+            group_end("el_2"),
+        ]);
+
+        let config = ConverterConfig::new().with_hidden_mode(HiddenMode::Display);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<g id="el_0"  display="none">"#));
+        assert!(!svg.contains("visibility="));
+        assert!(svg.contains(r#"<path id="el_1" d="M 0 0 l 4 4" />"#));
+    }
+
+    fn simple_shape(shape_type: SimpleShapeType, rotation: Option<i32>) -> WvgElement {
+        shape_in_box(shape_type, (0, 0, 10, 10), rotation)
+    }
+
+    /// A simple shape filling the `(x, y, width, height)` box.
+    fn shape_in_box(
+        shape_type: SimpleShapeType,
+        (x, y, width, height): (i32, i32, i32, i32),
+        rotation: Option<i32>,
+    ) -> WvgElement {
+        WvgElement {
+            id: "el_0".to_string(),
+            span: None,
+            data: ElementData::SimpleShape(SimpleShapeElement {
+                shape_type,
+                attributes: ElementAttributes::default(),
+                origin: Point::new(x, y),
+                width,
+                height,
+                rotation,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_rotated_rectangle() {
+        let doc = document(vec![shape_in_box(SimpleShapeType::Rectangle, (2, 4, 6, 8), Some(2))]);
+        let config = ConverterConfig::new().with_output_scale(2.0);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(
+            r#"<rect id="el_0" x="4" y="8" width="12" height="16" transform="rotate(45 10 16)" />"#
+        ));
+    }
+
+    #[test]
+    fn test_rotated_ellipse_path_matches_pivot() {
+        let doc = document(vec![shape_in_box(SimpleShapeType::Ellipse, (2, 4, 6, 8), Some(4))]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(
+            r#"<ellipse id="el_0" cx="5" cy="8" rx="3" ry="4" transform="rotate(90 5 8)" />"#
+        ));
+
+        // A quarter turn about (5, 8) maps the leftmost point (2, 8) to (5, 5)
+        let paths = crate::PathDataConverter::new().convert(&doc).unwrap();
+        assert!(paths[0].starts_with("M 5 5 "), "{}", paths[0]);
+    }
+
+    #[test]
+    fn test_minified_ellipse_gets_default_style() {
+        let doc = document(vec![simple_shape(SimpleShapeType::Ellipse, None)]);
+        let svg = SvgConverter::with_config(ConverterConfig::preset_web()).convert(&doc).unwrap();
+        assert!(svg.contains("<style>path,polyline,line,circle,ellipse,rect{stroke:#000;"));
+        assert!(svg.contains("<ellipse id=\"el_0\""));
+    }
+
+    #[test]
+    fn test_unrotated_ellipse() {
+        let doc = document(vec![simple_shape(SimpleShapeType::Ellipse, None)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"<ellipse id="el_0" cx="5" cy="5" rx="5" ry="5" />"#));
+    }
+
+    fn percent_decode(text: &str) -> String {
+        let bytes = text.as_bytes();
+        let mut decoded = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                i += 3;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    fn base64_decode(text: &str) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let values: Vec<u32> = text
+            .bytes()
+            .filter(|&b| b != b'=')
+            .map(|b| ALPHABET.iter().position(|&a| a == b).unwrap() as u32)
+            .collect();
+        let mut decoded = Vec::new();
+        for chunk in values.chunks(4) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |acc, (i, &v)| acc | v << (18 - 6 * i));
+            for i in 0..chunk.len() - 1 {
+                decoded.push((n >> (16 - 8 * i)) as u8);
+            }
+        }
+        String::from_utf8(decoded).unwrap()
+    }
+
+    #[test]
+    fn test_base64_encode() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn test_data_uri_percent() {
+        let doc = document(vec![polyline("el_0", &[(1, 1), (2, 3)])]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        let uri = SvgConverter::new().convert_data_uri(&doc).unwrap();
+
+        let payload = uri.strip_prefix("data:image/svg+xml;charset=utf-8,").unwrap();
+        assert!(!payload.contains(['<', '>', '"', '\'', '&', '#', ' ']));
+        assert_eq!(percent_decode(payload), svg);
+    }
+
+    #[test]
+    fn test_percent_encode_quote_and_ampersand() {
+        assert_eq!(percent_encode("a'b&c"), "a%27b%26c");
+        assert_eq!(percent_encode("x=1;y/z"), "x=1;y/z");
+    }
+
+    #[test]
+    fn test_data_uri_base64() {
+        let doc = document(vec![polyline("el_0", &[(1, 1), (2, 3)])]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        let config = ConverterConfig::new().with_data_uri_encoding(DataUriEncoding::Base64);
+        let uri = SvgConverter::with_config(config).convert_data_uri(&doc).unwrap();
+
+        let payload = uri.strip_prefix("data:image/svg+xml;base64,").unwrap();
+        assert_eq!(base64_decode(payload), svg);
+    }
+
+    #[test]
+    fn test_root_transform() {
+        let doc = document(vec![
+            group_start("el_0", true, None),
+            polyline("el_1", &[(1, 1), (2, 2)]),
+        ]);
+
+        let config = ConverterConfig::new().with_root_transform(10.0, 20.5, 2.0);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(
+            r#"<g transform="translate(10, 20.5) scale(2)"><g id="el_0" ><path id="el_1""#
+        ));
+        assert!(svg.ends_with("</g></g></svg>"));
+    }
+
+    #[test]
+    fn test_group_opacity() {
+        let doc = document(vec![
+            group_start("el_0", true, Some(0.5)),
+            polyline("el_1", &[(1, 1)]),
+            group_end("el_2"),
+        ]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"<g id="el_0"  opacity="0.5">"#));
+    }
+
+    #[test]
+    fn test_group_attributes_inherited() {
+        let red = Color::new(255, 0, 0);
+        let mut group = group_start("el_0", true, None);
+        if let ElementData::GroupStart(gs) = &mut group.data {
+            gs.attributes.line_color = Some(red);
+        }
+        let mut blue_line = polyline("el_2", &[(1, 1), (2, 2)]);
+        if let ElementData::Polyline(pl) = &mut blue_line.data {
+            pl.attributes.line_color = Some(Color::new(0, 0, 255));
+        }
+        let doc = document(vec![
+            group,
+            polyline("el_1", &[(1, 1), (2, 2)]),
+            blue_line,
+            group_end("el_3"),
+            polyline("el_4", &[(1, 1), (2, 2)]),
+        ]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r##"<path id="el_1" d="M 1 1 l 1 1" style="stroke: #ff0000"/>"##));
+        assert!(svg.contains(r##"<path id="el_2" d="M 1 1 l 1 1" style="stroke: #0000ff"/>"##));
+        assert!(svg.contains(r#"<path id="el_4" d="M 1 1 l 1 1" />"#));
+        assert_eq!(doc.effective_attributes(1).line_color, Some(red));
+        assert_eq!(doc.effective_attributes(4).line_color, None);
+
+        let config = ConverterConfig::new().with_css_classes(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"<path id="el_1" d="M 1 1 l 1 1" class="s1"/>"#));
+    }
+
+    fn local_envelope(id: &str, origin: (i32, i32), width: i32, height: i32) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::LocalEnvelope(LocalEnvelopeElement {
+                origin: Point::new(origin.0, origin.1),
+                width,
+                height,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_local_envelope_clips_rest_of_group() {
+        let doc = document(vec![
+            group_start("el_0", true, None),
+            local_envelope("el_1", (2, 2), 4, 4),
+            polyline("el_2", &[(0, 0), (10, 10)]),
+            group_end("el_3"),
+            polyline("el_4", &[(0, 0), (10, 10)]),
+        ]);
+        let config = ConverterConfig::new().with_pretty_print(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        let start = svg.find("<clipPath").unwrap();
+        let lines: Vec<&str> = svg[start..].lines().map(str::trim).collect();
+        assert_eq!(&lines[..8], &[
+            r#"<clipPath id="el_1_clip">"#,
+            r#"<rect x="2" y="2" width="4" height="4"/>"#,
+            "</clipPath>",
+            r#"<g id="el_1" clip-path="url(#el_1_clip)">"#,
+            r#"<path id="el_2" d="M 0 0 l 10 10" />"#,
+            "</g>",
+            "</g>",
+            r#"<path id="el_4" d="M 0 0 l 10 10" />"#,
+        ]);
+    }
+
+    #[test]
+    fn test_local_envelope_at_top_level() {
+        let doc = document(vec![
+            local_envelope("el_0", (0, 0), 5, 5),
+            polyline("el_1", &[(0, 0), (10, 10)]),
+        ]);
+        let config = ConverterConfig::new().with_id_prefix("a_");
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        assert!(svg.contains(r#"<g id="a_el_0" clip-path="url(#a_el_0_clip)">"#));
+        assert!(svg.contains(r#"<clipPath id="a_el_0_clip">"#));
+        assert!(svg.ends_with("</g></svg>"));
+    }
+
+    #[test]
+    fn test_array_reuse_explicit() {
+        let doc = document(vec![
+            polyline("el_0", &[(0, 0), (2, 2)]),
+            array_reuse("el_1", 0, 4, 4),
+        ]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert_eq!(svg.matches("<use ").count(), 16);
+    }
+
+    #[test]
+    fn test_array_reuse_nested() {
+        let doc = document(vec![
+            polyline("el_0", &[(0, 0), (2, 2)]),
+            array_reuse("el_1", 0, 4, 4),
+        ]);
+        let config = ConverterConfig::new().with_array_as_nested_use(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        assert!(svg.matches("<use ").count() < 16);
+        assert!(svg.contains(r##"<use id="el_1_0_3" href="#el_0" transform="translate(24, 0)" />"##));
+        assert!(svg.contains(r##"<use id="el_1_3" href="#el_1_row" transform="translate(0, 12)" />"##));
+    }
+
+    /// A closed, filled five-pointed star drawn as a single polyline.
+    fn filled_star() -> WvgElement {
+        let points = [(50, 0), (79, 90), (2, 35), (98, 35), (21, 90), (50, 0)];
+        WvgElement {
+            id: "el_0".to_string(),
+            span: None,
+            data: ElementData::Polyline(PolylineElement {
+                attributes: ElementAttributes {
+                    fill: Some(true),
+                    ..Default::default()
+                },
+                points: points.iter().map(|&(x, y)| Point::new(x, y)).collect(),
+            }),
+        }
+    }
+
+    fn styled_polyline(id: &str, line_width: LineWidth) -> WvgElement {
+        let mut element = polyline(id, &[(0, 0), (4, 4)]);
+        if let ElementData::Polyline(pl) = &mut element.data {
+            pl.attributes.line_width = Some(line_width);
+        }
+        element
+    }
+
+    fn grid(columns: u32, rows: u32) -> WvgElement {
+        WvgElement {
+            id: "el_0".to_string(),
+            span: None,
+            data: ElementData::SpecialShape(SpecialShapeElement {
+                shape: SpecialShape::Grid(GridShape {
+                    origin: Point::new(2, 4),
+                    columns,
+                    rows,
+                    cell_width: 10,
+                    cell_height: 5,
+                }),
+                attributes: ElementAttributes {
+                    line_color: Some(Color::new(255, 0, 0)),
+                    ..Default::default()
+                },
+            }),
+        }
+    }
+
+    fn star(num_points: u32, outer_radius: i32, inner_radius: i32) -> WvgElement {
+        WvgElement {
+            id: "el_0".to_string(),
+            span: None,
+            data: ElementData::SpecialShape(SpecialShapeElement {
+                shape: SpecialShape::Star(StarShape {
+                    center: Point::new(10, 10),
+                    num_points,
+                    outer_radius,
+                    inner_radius,
+                    rotation: None,
+                }),
+                attributes: ElementAttributes::default(),
+            }),
+        }
+    }
+
+    /// Returns the vertices of the first `<polygon>` in `svg`.
+    fn polygon_points(svg: &str) -> Vec<(f64, f64)> {
+        let points = svg.split("points=\"").nth(1).expect("no polygon");
+        points[..points.find('"').unwrap()]
+            .split(' ')
+            .map(|pair| {
+                let (x, y) = pair.split_once(',').unwrap();
+                (x.parse().unwrap(), y.parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_star_polygon() {
+        let doc = document(vec![star(5, 10, 4)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+
+        assert!(svg.contains("rect, polygon {"));
+        let points = polygon_points(&svg);
+        assert_eq!(points.len(), 10);
+        // Tips on the outer circle starting at the top, inner vertices between
+        assert_eq!(points[0], (10.0, 0.0));
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let radius = ((x - 10.0).powi(2) + (y - 10.0).powi(2)).sqrt();
+            let expected = if i % 2 == 0 { 10.0 } else { 4.0 };
+            assert!((radius - expected).abs() < 0.01, "vertex {}: {:?}", i, (x, y));
+        }
+    }
+
+    #[test]
+    fn test_star_equal_radii() {
+        // Equal radii give a regular polygon with twice as many corners
+        let doc = document(vec![star(3, 10, 10)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert_eq!(polygon_points(&svg), vec![
+            (10.0, 0.0),
+            (18.66, 5.0),
+            (18.66, 15.0),
+            (10.0, 20.0),
+            (1.34, 15.0),
+            (1.34, 5.0),
+        ]);
+    }
+
+    #[test]
+    fn test_star_vertex_limit() {
+        let doc = document(vec![star(u32::MAX / 2, 10, 4)]);
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(
+            result,
+            Err(WvgError::LimitExceeded { what: "star vertex count", limit: 4096, .. })
+        ));
+    }
+
+    #[test]
+    fn test_star_without_points() {
+        let doc = document(vec![star(1, 10, 4)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(!svg.contains("<polygon"));
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let doc = document(vec![grid(3, 2)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+
+        assert!(svg.contains(r#"<g id="el_0">"#));
+        assert_eq!(svg.matches("<line ").count(), 3 + 4);
+        assert!(svg.contains(r#"<line x1="2" y1="14" x2="32" y2="14" style="stroke: #ff0000"/>"#));
+        assert!(svg.contains(r#"<line x1="32" y1="4" x2="32" y2="14" style="stroke: #ff0000"/>"#));
+    }
+
+    #[test]
+    fn test_grid_line_limit() {
+        let doc = document(vec![grid(100_000, 2)]);
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(
+            result,
+            Err(WvgError::LimitExceeded { what: "grid line count", value: 100_004, limit: 4096 })
+        ));
+
+        let config = ConverterConfig::new().with_max_array_instances(3 + 4);
+        assert!(SvgConverter::with_config(config).convert(&document(vec![grid(3, 2)])).is_ok());
+        let config = ConverterConfig::new().with_max_array_instances(3 + 4 - 1);
+        assert!(SvgConverter::with_config(config).convert(&document(vec![grid(3, 2)])).is_err());
+    }
+
+    #[test]
+    fn test_grid_single_cell() {
+        let doc = document(vec![grid(1, 1)]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+
+        assert!(!svg.contains("<line "));
+        assert!(svg.contains(
+            r#"<rect id="el_0" x="2" y="4" width="10" height="5" style="stroke: #ff0000"/>"#
+        ));
+    }
+
+    #[test]
+    fn test_css_classes_shared() {
+        let doc = document(vec![
+            styled_polyline("el_0", LineWidth::Thick),
+            styled_polyline("el_1", LineWidth::Normal),
+            styled_polyline("el_2", LineWidth::Thick),
+            polyline("el_3", &[(1, 1), (2, 2)]),
+        ]);
+        let config = ConverterConfig::new().with_css_classes(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        assert!(svg.contains(" .s1 { stroke-width: 3; } .s2 { stroke-width: 2; }</style>"));
+        assert!(svg.contains(r#"<path id="el_0" d="M 0 0 l 4 4" class="s1"/>"#));
+        assert!(svg.contains(r#"<path id="el_1" d="M 0 0 l 4 4" class="s2"/>"#));
+        assert!(svg.contains(r#"<path id="el_2" d="M 0 0 l 4 4" class="s1"/>"#));
+        assert!(svg.contains(r#"<path id="el_3" d="M 1 1 l 1 1" />"#));
+        assert!(!svg.contains("style=\""));
+    }
+
+    #[test]
+    fn test_translucent_colors() {
+        let mut element = filled_star();
+        if let ElementData::Polyline(pl) = &mut element.data {
+            pl.attributes.line_color = Some(Color::rgba(255, 0, 0, 128));
+            pl.attributes.fill_color = Some(Color::new(0, 0, 255));
+        }
+        let doc = document(vec![element]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+
+        assert!(svg.contains("stroke: #ff0000; stroke-opacity: 0.502;"));
+        assert!(svg.contains("fill: #0000ff; fill-rule"));
+        assert!(!svg.contains("fill-opacity"));
+    }
+
+    #[test]
+    fn test_short_hex() {
+        assert_eq!(color_to_short_hex(&Color::BLACK), "#000");
+        assert_eq!(color_to_short_hex(&Color::new(0xff, 0x33, 0xaa)), "#f3a");
+        assert_eq!(color_to_short_hex(&Color::new(0xff, 0x34, 0xaa)), "#ff34aa");
+    }
+
+    #[test]
+    fn test_default_stroke_width_scaled() {
+        let doc = document(vec![polyline("el_0", &[(1, 1), (4, 5)])]);
+        let config = ConverterConfig::new().with_line_width_scale(2.0);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains("stroke-width: 2; }"));
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains("stroke-width: 1; }"));
+    }
+
+    #[test]
+    fn test_aria_attributes() {
+        let mut doc = document(vec![
+            group_start("el_0", true, None),
+            polyline("el_1", &[(1, 1), (4, 5)]),
+            group_end("el_2"),
+        ]);
+        doc.header.general_info.title = Some("Cat & mouse".to_string());
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(!svg.contains("role="));
+
+        let config = ConverterConfig::new().with_aria(true);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 128 32" role="img" aria-label="Cat &amp; mouse">"#));
+        assert!(svg.contains(r#"role="group" aria-label="Group 1">"#));
+    }
+
+    #[test]
+    fn test_without_xml_declaration() {
+        let doc = document(vec![polyline("el_0", &[(1, 1), (4, 5)])]);
+        let config = ConverterConfig::new().with_pretty_print(true).with_xml_declaration(false);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains('\n'));
+    }
+
+    #[test]
+    fn test_wrap_path_data() {
+        let points: Vec<(i32, i32)> = (0..20).map(|i| (i * 5, (i % 2) * 10)).collect();
+        let doc = document(vec![polyline("el_0", &points)]);
+        let config = ConverterConfig::new().with_wrap_path_data(20);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+
+        assert!(svg.contains("d=\"M 0 0 l 5 10 l 5 -10\nl 5 10 l 5 -10\nl 5 10"));
+        assert_eq!(wrap_path_data("M 10 20 L 300 400", 4), "M 10 20\nL 300 400");
+    }
+
+    #[test]
+    fn test_minify_path_data() {
+        assert_eq!(minify_path_data("M 10 20 l 3 -4 l -1 0"), "M10 20l3-4l-1 0");
+        assert_eq!(
+            minify_path_data("M 0 0 A 5.5 5.5 0 0 1 -3 2 L 7 8"),
+            "M0 0A5.5 5.5 0 0 1-3 2L7 8"
+        );
+    }
+
+    #[test]
+    fn test_alpha_to_opacity() {
+        assert_eq!(alpha_to_opacity(0), "0");
+        assert_eq!(alpha_to_opacity(51), "0.2");
+        assert_eq!(alpha_to_opacity(128), "0.502");
+        assert_eq!(alpha_to_opacity(255), "1");
+    }
+
+    #[test]
+    fn test_zero_line_width_has_no_stroke() {
+        let mut element = filled_star();
+        if let ElementData::Polyline(pl) = &mut element.data {
+            pl.attributes.line_width = Some(LineWidth::None);
+            pl.attributes.line_color = Some(Color::new(255, 0, 0));
+        }
+        let doc = document(vec![element]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"style="stroke: none; fill: currentColor; fill-rule: nonzero""#));
+    }
+
+    #[test]
+    fn test_fill_rule_default() {
+        let doc = document(vec![filled_star()]);
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"style="fill: currentColor; fill-rule: nonzero""#));
+    }
+
+    #[test]
+    fn test_fill_rule_evenodd() {
+        let doc = document(vec![filled_star()]);
+        let config = ConverterConfig::new().with_fill_rule(FillRule::EvenOdd);
+        let svg = SvgConverter::with_config(config).convert(&doc).unwrap();
+        assert!(svg.contains(r#"style="fill: currentColor; fill-rule: evenodd""#));
+    }
+
+    #[test]
+    fn test_fill_uses_default_fill_color() {
+        let mut doc = document(vec![filled_star()]);
+        doc.header.color_config.default_fill_color = Some(Color::new(0, 128, 255));
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r##"style="fill: #0080ff; fill-rule: nonzero""##));
+    }
+
+    /// Builds a circular polyline from (curve offset, point, is absolute) triples.
+    fn circular_polyline(id: &str, points: &[(i32, Point, bool)]) -> WvgElement {
+        WvgElement {
+            id: id.to_string(),
+            span: None,
+            data: ElementData::CircularPolyline(CircularPolylineElement {
+                attributes: ElementAttributes::default(),
+                points: points
+                    .iter()
+                    .map(|&(curve_offset, point, is_absolute)| CircularPoint {
+                        curve_offset,
+                        point,
+                        is_absolute,
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_circular_polyline_overflow() {
+        let doc = document(vec![circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(0, 0), true),
+                (0, Point::new(i32::MAX - 1, 0), true),
+                (0, Point::new(5, 0), false),
+            ],
+        )]);
+
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(result, Err(WvgError::CoordinateOverflow)));
+    }
+
+    #[test]
+    fn test_mirrored_arcs_swap_sweep() {
+        let element = circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(10, 0), true),
+                (3, Point::new(20, 0), true),
+                (-5, Point::new(0, 10), false),
+            ],
+        );
+        let mut doc = document(vec![element.clone()]);
+        if let CoordinateParams::Flat(params) = &mut doc.header.codec_params.coord_params {
+            params.xy_all_positive = false;
+        }
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 10 0 A 6.90 6.90 0 0 1 20 0 A 5.29 5.29 0 0 0 20 10""#));
+
+        let ElementData::CircularPolyline(cp) = &element.data else {
+            unreachable!()
+        };
+        doc.elements[0].data = ElementData::CircularPolyline(cp.mirrored_x());
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"d="M -10 0 A 6.90 6.90 0 0 0 -20 0 A 5.29 5.29 0 0 1 -20 10""#));
+    }
+
+    #[test]
+    fn test_degenerate_arc_drawn_as_line() {
+        let doc = document(vec![circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(0, 0), true),
+                (1, Point::new(100_000, 0), true),
+            ],
+        )]);
+
+        let (svg, warnings) = SvgConverter::new().convert_with_warnings(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 0 0 L 100000 0""#));
+        assert_eq!(warnings, vec![ParseWarning::DegenerateArc { element_index: 0 }]);
+    }
+
+    #[test]
+    fn test_regular_arc_has_no_warnings() {
+        let doc = document(vec![circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(0, 0), true),
+                (1, Point::new(10, 0), true),
+            ],
+        )]);
+
+        let (svg, warnings) = SvgConverter::new().convert_with_warnings(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 0 0 A "#));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_relative_second_circular_point() {
+        let doc = document(vec![circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(10, 10), true),
+                (0, Point::new(2, -1), false),
+            ],
+        )]);
+
+        let svg = SvgConverter::new().convert(&doc).unwrap();
+        assert!(svg.contains(r#"d="M 10 10 l 2 -1""#));
+    }
+
+    #[test]
+    fn test_straight_circular_polyline_matches_polyline() {
+        let circular = document(vec![circular_polyline(
+            "el_0",
+            &[
+                (0, Point::new(1, 2), true),
+                (0, Point::new(5, 6), true),
+                (0, Point::new(2, -1), false),
+            ],
+        )]);
+        let plain = document(vec![polyline("el_0", &[(1, 2), (5, 6), (7, 5)])]);
+
+        let converter = SvgConverter::new();
+        let svg = converter.convert(&circular).unwrap();
+        assert!(svg.contains(r#"d="M 1 2 l 4 4 l 2 -1""#));
+        assert_eq!(svg, converter.convert(&plain).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_angle_resolution() {
+        let mut doc = document(vec![polyline("el_0", &[(1, 1)])]);
+        doc.header.codec_params.generic_params.angle_resolution = 4;
+
+        let result = SvgConverter::new().convert(&doc);
+        assert!(matches!(result, Err(WvgError::InvalidAngleResolution(4))));
+    }
+
+    #[test]
+    fn test_checked_offset_near_max() {
+        let p = Point::new(i32::MAX - 1, i32::MIN + 1);
+        assert!(p.checked_offset(1, -1).is_some());
+        assert!(p.checked_offset(2, 0).is_none());
+        assert!(p.checked_offset(0, -2).is_none());
+    }
+
+    #[test]
+    fn test_arc_flags_rightward() {
+        // Travelling right, a positive offset bulges up (clockwise on screen)
+        assert_eq!(arc_flags(10.0, 0.0, 2.0), (0, 1));
+        assert_eq!(arc_flags(10.0, 0.0, -2.0), (0, 0));
+    }
+
+    #[test]
+    fn test_arc_flags_downward() {
+        // Travelling down, a positive offset bulges right (clockwise on screen)
+        assert_eq!(arc_flags(0.0, 10.0, 2.0), (0, 1));
+        assert_eq!(arc_flags(0.0, 10.0, -2.0), (0, 0));
+    }
+
+    #[test]
+    fn test_arc_flags_leftward() {
+        // Travelling left, a positive offset bulges down (clockwise on screen)
+        assert_eq!(arc_flags(-10.0, 0.0, 2.0), (0, 1));
+        assert_eq!(arc_flags(-10.0, 0.0, -2.0), (0, 0));
+    }
+
+    #[test]
+    fn test_arc_flags_upward() {
+        // Travelling up, a positive offset bulges left (clockwise on screen)
+        assert_eq!(arc_flags(0.0, -10.0, 2.0), (0, 1));
+        assert_eq!(arc_flags(0.0, -10.0, -2.0), (0, 0));
+    }
+
+    #[test]
+    fn test_arc_flags_large_arc() {
+        // An offset beyond half the chord length spans more than 180 degrees
+        assert_eq!(arc_flags(10.0, 0.0, 6.0), (1, 1));
+        assert_eq!(arc_flags(10.0, 0.0, -6.0), (1, 0));
+        assert_eq!(arc_flags(10.0, 0.0, 5.0), (0, 1));
+    }
+}
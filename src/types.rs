@@ -3,8 +3,13 @@
 //! This module defines all the data types used to represent a parsed WVG document,
 //! including elements, attributes, transforms, and coordinate parameters.
 
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use crate::error::{UnsupportedFeature, WvgError, WvgResult};
+
 /// A parsed WVG document containing all header information and elements.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WvgDocument {
     /// The WVG header containing metadata and codec parameters.
     pub header: WvgHeader,
@@ -12,8 +17,289 @@ pub struct WvgDocument {
     pub elements: Vec<WvgElement>,
 }
 
+impl IntoIterator for WvgDocument {
+    type Item = WvgElement;
+    type IntoIter = std::vec::IntoIter<WvgElement>;
+
+    /// Consumes the document, yielding its elements in order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.elements.into_iter()
+    }
+}
+
+impl WvgDocument {
+    /// Returns the element at the given index in the element list.
+    pub fn element_at(&self, index: usize) -> Option<&WvgElement> {
+        self.elements.get(index)
+    }
+
+    /// Removes all elements from the document, yielding them by value.
+    ///
+    /// Reuse elements refer to elements by index, so their references are
+    /// meaningless once the elements are taken out.
+    pub fn drain_elements(&mut self) -> std::vec::Drain<'_, WvgElement> {
+        self.elements.drain(..)
+    }
+
+    /// Returns the element referenced by a reuse element.
+    pub fn resolve_reuse(&self, reuse: &ReuseElement) -> Option<&WvgElement> {
+        self.element_at(reuse.element_index as usize)
+    }
+
+    /// Replaces every occurrence of `from` with `to` in element attributes,
+    /// reuse override attributes, the default and background colors, and the
+    /// palette. Colors only match if their alpha is equal as well.
+    pub fn replace_color(&mut self, from: Color, to: Color) {
+        let replace = |color: &mut Option<Color>| {
+            if *color == Some(from) {
+                *color = Some(to);
+            }
+        };
+
+        let cc = &mut self.header.color_config;
+        replace(&mut cc.default_line_color);
+        replace(&mut cc.default_fill_color);
+        replace(&mut cc.background_color);
+        for color in cc.palette.iter_mut().filter(|c| **c == from) {
+            *color = to;
+        }
+
+        for element in &mut self.elements {
+            let attrs = match &mut element.data {
+                ElementData::Polyline(pl) => Some(&mut pl.attributes),
+                ElementData::CircularPolyline(cp) => Some(&mut cp.attributes),
+                ElementData::SimpleShape(ss) => Some(&mut ss.attributes),
+                ElementData::SpecialShape(ss) => Some(&mut ss.attributes),
+                ElementData::Reuse(reuse) => reuse.override_attributes.as_mut(),
+                ElementData::GroupStart(gs) => Some(&mut gs.attributes),
+                ElementData::GroupEnd | ElementData::LocalEnvelope(_) => None,
+            };
+
+            if let Some(attrs) = attrs {
+                replace(&mut attrs.line_color);
+                replace(&mut attrs.fill_color);
+            }
+        }
+    }
+
+    /// Returns the attributes of the element at `element_index`, with unset
+    /// fields inherited from the enclosing groups and line and fill colors
+    /// falling back to the document defaults.
+    ///
+    /// A reuse element takes the attributes of the element it references,
+    /// with its override attributes applied on top. Groups and indices out of
+    /// range yield the inherited attributes and defaults alone.
+    pub fn effective_attributes(&self, element_index: usize) -> ElementAttributes {
+        let attrs = self
+            .element_at(element_index)
+            .map(|element| self.own_attributes(element))
+            .unwrap_or_default();
+
+        let attrs = attrs.or(&self.group_attributes(element_index));
+        self.header.color_config.resolve(&attrs)
+    }
+
+    /// Returns the attributes set on `element` itself, resolving reuse
+    /// elements to their target with the overrides applied on top.
+    fn own_attributes(&self, element: &WvgElement) -> ElementAttributes {
+        match &element.data {
+            ElementData::Reuse(reuse) => {
+                let base = self
+                    .resolve_reuse(reuse)
+                    .and_then(|target| target.data.attributes())
+                    .cloned()
+                    .unwrap_or_default();
+                match &reuse.override_attributes {
+                    Some(overrides) => overrides.or(&base),
+                    None => base,
+                }
+            }
+            data => data.attributes().cloned().unwrap_or_default(),
+        }
+    }
+
+    /// Returns the attributes inherited from the groups enclosing the
+    /// element at `element_index`.
+    fn group_attributes(&self, element_index: usize) -> ElementAttributes {
+        let mut stack = vec![ElementAttributes::default()];
+        for element in self.elements.iter().take(element_index) {
+            match &element.data {
+                ElementData::GroupStart(gs) => {
+                    let inherited = gs.attributes.or(stack.last().unwrap());
+                    stack.push(inherited);
+                }
+                ElementData::GroupEnd if stack.len() > 1 => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+        stack.pop().unwrap()
+    }
+
+    /// Returns the group nesting depth of every element, in element order.
+    ///
+    /// Elements outside any group have depth 0. A group start and its
+    /// matching group end share the depth of the enclosing group, so the
+    /// levels can be used for indentation directly. Unmatched group ends
+    /// are ignored.
+    pub fn nesting_levels(&self) -> Vec<usize> {
+        let mut depth = 0usize;
+        self.elements
+            .iter()
+            .map(|element| match element.data {
+                ElementData::GroupStart(_) => {
+                    depth += 1;
+                    depth - 1
+                }
+                ElementData::GroupEnd => {
+                    depth = depth.saturating_sub(1);
+                    depth
+                }
+                _ => depth,
+            })
+            .collect()
+    }
+
+    /// Returns true if the document declares an animation mode or contains
+    /// animation or frame elements.
+    pub fn is_animated(&self) -> bool {
+        self.header.animation_mode.is_some()
+            || self.elements.iter().any(|element| match element.data {
+                // Animation and frame elements are not parsed yet; they
+                // belong here once they have variants
+                ElementData::Polyline(_)
+                | ElementData::CircularPolyline(_)
+                | ElementData::SimpleShape(_)
+                | ElementData::SpecialShape(_)
+                | ElementData::Reuse(_)
+                | ElementData::GroupStart(_)
+                | ElementData::GroupEnd
+                | ElementData::LocalEnvelope(_) => false,
+            })
+    }
+
+    /// Returns the distinct colors the document draws with, in order of
+    /// first use.
+    ///
+    /// Covers the resolved line color of every stroked element, the resolved
+    /// fill color of every filled element (both BLACK if unset) and the
+    /// background color if one is declared. Reuse elements contribute their
+    /// override colors; groups contribute nothing.
+    pub fn distinct_colors(&self) -> Vec<Color> {
+        let mut colors = Vec::new();
+        let mut seen = HashSet::new();
+        let mut add = |color: Color| {
+            if seen.insert(color) {
+                colors.push(color);
+            }
+        };
+
+        // Track the group attributes in one pass rather than rescanning the
+        // elements before each one
+        let mut inherited = vec![ElementAttributes::default()];
+        for element in &self.elements {
+            match &element.data {
+                ElementData::GroupStart(gs) => {
+                    inherited.push(gs.attributes.or(inherited.last().unwrap()));
+                    continue;
+                }
+                ElementData::GroupEnd => {
+                    if inherited.len() > 1 {
+                        inherited.pop();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            let attrs = self.own_attributes(element).or(inherited.last().unwrap());
+            let attrs = self.header.color_config.resolve(&attrs);
+            if attrs.line_width != Some(LineWidth::None) {
+                add(attrs.line_color.unwrap_or(Color::BLACK));
+            }
+            if attrs.fill == Some(true) {
+                add(attrs.fill_color.unwrap_or(Color::BLACK));
+            }
+        }
+
+        if let Some(background) = self.header.color_config.background_color {
+            add(background);
+        }
+        colors
+    }
+
+    /// Returns element counts and other summary figures for this document.
+    pub fn statistics(&self) -> DocumentStats {
+        let mut stats = DocumentStats {
+            palette_size: self.header.color_config.palette.len(),
+            ..Default::default()
+        };
+
+        for element in &self.elements {
+            match &element.data {
+                ElementData::Polyline(pl) => {
+                    stats.polylines += 1;
+                    stats.total_points += pl.points.len();
+                }
+                ElementData::CircularPolyline(cp) => {
+                    stats.circular_polylines += 1;
+                    stats.total_points += cp.points.len();
+                }
+                ElementData::SimpleShape(_) => stats.simple_shapes += 1,
+                ElementData::SpecialShape(_) => stats.special_shapes += 1,
+                ElementData::Reuse(_) => stats.reuses += 1,
+                ElementData::GroupStart(_) => stats.groups += 1,
+                ElementData::LocalEnvelope(_) => stats.local_envelopes += 1,
+                ElementData::GroupEnd => {}
+            }
+        }
+
+        let codec_params = &self.header.codec_params;
+        let report = FeatureReport {
+            character_size: false,
+            compact_coordinates: matches!(codec_params.coord_params, CoordinateParams::Compact(_)),
+            element_types: codec_params
+                .element_masks
+                .iter()
+                .enumerate()
+                .filter(|&(_, &enabled)| enabled)
+                .filter_map(|(i, _)| ElementType::from_mask_index(i))
+                .collect(),
+            animation_mode: self.header.animation_mode,
+        };
+        stats.unsupported_features = report.unsupported_features();
+
+        stats
+    }
+}
+
+/// Summary figures for a parsed document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentStats {
+    /// Number of polyline elements.
+    pub polylines: usize,
+    /// Number of circular polyline elements.
+    pub circular_polylines: usize,
+    /// Number of simple shape elements.
+    pub simple_shapes: usize,
+    /// Number of special shape elements.
+    pub special_shapes: usize,
+    /// Number of reuse elements.
+    pub reuses: usize,
+    /// Number of groups.
+    pub groups: usize,
+    /// Number of local envelope elements.
+    pub local_envelopes: usize,
+    /// Total number of points across all polylines.
+    pub total_points: usize,
+    /// Number of colors in the custom palette.
+    pub palette_size: usize,
+    /// Unsupported features declared in the header.
+    pub unsupported_features: Vec<UnsupportedFeature>,
+}
+
 /// WVG document header containing all header information.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WvgHeader {
     /// General information about the WVG.
     pub general_info: GeneralInfo,
@@ -25,8 +311,26 @@ pub struct WvgHeader {
     pub animation_mode: Option<AnimationMode>,
 }
 
+/// Header of a Character Size WVG.
+///
+/// The general information and color configuration are read with the same
+/// layout as in a Standard WVG header. The character-size-specific fields
+/// after them are not decoded yet and are kept as raw bits for inspection.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CharacterSizeHeader {
+    /// General information about the WVG.
+    pub general_info: GeneralInfo,
+    /// Color configuration.
+    pub color_config: ColorConfig,
+    /// The bits following the color configuration, MSB-first; the last byte
+    /// is padded with zero bits.
+    pub remaining_bits: Vec<u8>,
+    /// Number of meaningful bits in `remaining_bits`.
+    pub remaining_bit_len: usize,
+}
+
 /// General information from the WVG header.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct GeneralInfo {
     /// WVG format version.
     pub version: u8,
@@ -41,7 +345,7 @@ pub struct GeneralInfo {
 }
 
 /// Text encoding mode.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TextCodeMode {
     /// 7-bit GSM character set.
     Gsm7Bit,
@@ -50,7 +354,7 @@ pub enum TextCodeMode {
 }
 
 /// Timestamp information.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Timestamp {
     pub year: i16,
     pub month: u8,
@@ -60,8 +364,87 @@ pub struct Timestamp {
     pub second: u8,
 }
 
+impl Timestamp {
+    /// Returns true if all fields form a valid calendar date and time of day.
+    pub fn is_valid(&self) -> bool {
+        (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && self.hour <= 23
+            && self.minute <= 59
+            && self.second <= 59
+    }
+
+    /// Returns a copy with every field clamped into its valid range.
+    pub fn clamped(&self) -> Self {
+        let month = self.month.clamp(1, 12);
+        Self {
+            year: self.year,
+            month,
+            day: self.day.clamp(1, days_in_month(self.year, month)),
+            hour: self.hour.min(23),
+            minute: self.minute.min(59),
+            second: self.second.min(59),
+        }
+    }
+
+    /// Formats the timestamp as an RFC 3339 date-time.
+    ///
+    /// WVG timestamps carry no time zone, so UTC is assumed. Returns `None`
+    /// if the fields do not form a valid date or the year is outside
+    /// `0..=9999`.
+    pub fn to_rfc3339(&self) -> Option<String> {
+        if !self.is_valid() || !(0..=9999).contains(&self.year) {
+            return None;
+        }
+        Some(format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        ))
+    }
+
+    /// Returns the timestamp as seconds since the Unix epoch, assuming UTC.
+    ///
+    /// Dates before 1970 yield negative values. Returns `None` if the fields
+    /// do not form a valid date.
+    pub fn to_unix(&self) -> Option<i64> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        // Days since 1970-01-01 in the proleptic Gregorian calendar, counted
+        // in 400-year eras starting on March 1st so leap days come last
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - 719_468;
+
+        let seconds =
+            i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second);
+        Some(days * 86_400 + seconds)
+    }
+}
+
+/// Returns the number of days in the given month of the proleptic Gregorian calendar.
+fn days_in_month(year: i16, month: u8) -> u8 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 => {
+            let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+            if leap {
+                29
+            } else {
+                28
+            }
+        }
+        _ => 31,
+    }
+}
+
 /// Color configuration.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ColorConfig {
     /// The color scheme used in this document.
     pub scheme: ColorScheme,
@@ -71,6 +454,8 @@ pub struct ColorConfig {
     pub default_fill_color: Option<Color>,
     /// Background color (WHITE if not specified).
     pub background_color: Option<Color>,
+    /// Custom palette (only for the palette color schemes).
+    pub palette: Vec<Color>,
 }
 
 impl Default for ColorConfig {
@@ -80,12 +465,25 @@ impl Default for ColorConfig {
             default_line_color: None,
             default_fill_color: None,
             background_color: None,
+            palette: Vec::new(),
+        }
+    }
+}
+
+impl ColorConfig {
+    /// Returns `attrs` with missing line and fill colors taken from the
+    /// document defaults.
+    pub fn resolve(&self, attrs: &ElementAttributes) -> ElementAttributes {
+        ElementAttributes {
+            line_color: attrs.line_color.or(self.default_line_color),
+            fill_color: attrs.fill_color.or(self.default_fill_color),
+            ..attrs.clone()
         }
     }
 }
 
 /// Available color schemes in WVG.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ColorScheme {
     /// Black and white (2 colors).
     BlackAndWhite,
@@ -108,17 +506,46 @@ pub enum ColorScheme {
 }
 
 /// A color value.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    /// Alpha channel (255 = fully opaque).
+    pub a: u8,
 }
 
 impl Color {
-    /// Creates a new color with the given RGB values.
+    /// Creates a new fully opaque color with the given RGB values.
     pub const fn new(r: u8, g: u8, b: u8) -> Self {
-        Self { r, g, b }
+        Self { r, g, b, a: 255 }
+    }
+
+    /// Creates a new color with the given RGB and alpha values.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Returns true if the color is fully opaque.
+    pub const fn is_opaque(&self) -> bool {
+        self.a == 255
+    }
+
+    /// Returns the Euclidean distance to `other` in RGB space, ignoring
+    /// alpha. Ranges from 0 to about 441.7 (black to white).
+    pub fn distance(&self, other: &Color) -> f64 {
+        let dr = f64::from(self.r) - f64::from(other.r);
+        let dg = f64::from(self.g) - f64::from(other.g);
+        let db = f64::from(self.b) - f64::from(other.b);
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+
+    /// Returns the color in `palette` closest to this one, preferring the
+    /// earliest on ties, or `None` for an empty palette.
+    pub fn nearest<'a>(&self, palette: &'a [Color]) -> Option<&'a Color> {
+        palette
+            .iter()
+            .min_by(|a, b| self.distance(a).total_cmp(&self.distance(b)))
     }
 
     /// Black color.
@@ -128,7 +555,7 @@ impl Color {
 }
 
 /// Codec parameters for parsing elements.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CodecParams {
     /// Element mask indicating which element types are present.
     pub element_masks: Vec<bool>,
@@ -141,7 +568,7 @@ pub struct CodecParams {
 }
 
 /// Attribute masks indicating which attributes are used.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct AttributeMasks {
     /// True if line type attribute is used.
     pub line_type: bool,
@@ -154,7 +581,7 @@ pub struct AttributeMasks {
 }
 
 /// Generic parameters for angles, scales, and indices.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GenericParams {
     /// Angle resolution (determines angle unit).
     pub angle_resolution: u8,
@@ -183,8 +610,26 @@ impl Default for GenericParams {
     }
 }
 
+impl GenericParams {
+    /// Returns the angle unit in degrees.
+    ///
+    /// The default resolution code 3 gives 22.5 degrees, and each lower code
+    /// halves the unit.
+    pub fn angle_degrees(&self) -> f64 {
+        22.5 / f64::from(1u32 << 3u8.saturating_sub(self.angle_resolution))
+    }
+
+    /// Returns the scale unit, i.e. the scale change per step.
+    ///
+    /// The default resolution code 0 gives 1/4, and each higher code halves
+    /// the unit.
+    pub fn scale_step(&self) -> f64 {
+        0.25 / f64::from(1u32 << self.scale_resolution.min(3))
+    }
+}
+
 /// Coordinate system parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CoordinateParams {
     /// Flat coordinate system parameters.
     Flat(FlatCoordinateParams),
@@ -192,8 +637,18 @@ pub enum CoordinateParams {
     Compact(CompactCoordinateParams),
 }
 
+impl CoordinateParams {
+    /// Returns the declared drawing size as `(width, height)`.
+    pub fn dimensions(&self) -> (u16, u16) {
+        match self {
+            CoordinateParams::Flat(params) => (params.drawing_width, params.drawing_height),
+            CoordinateParams::Compact(params) => (params.width, params.height),
+        }
+    }
+}
+
 /// Flat coordinate system parameters.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FlatCoordinateParams {
     /// Drawing width in pixels.
     pub drawing_width: u16,
@@ -219,14 +674,43 @@ pub struct FlatCoordinateParams {
     pub offset_y_in_bits_level2: u8,
 }
 
-/// Compact coordinate system parameters (stub for future implementation).
-#[derive(Debug, Clone, Default)]
+impl FlatCoordinateParams {
+    /// Checks that the declared bit widths describe decodable coordinates.
+    ///
+    /// At least one coordinate width must be nonzero. Offset and translation
+    /// widths are not compared against the coordinate widths, since wider
+    /// fields still decode.
+    pub fn validate(&self) -> WvgResult<()> {
+        if self.max_x_in_bits == 0 && self.max_y_in_bits == 0 {
+            return Err(WvgError::InvalidCoordinateParams(
+                "both coordinate widths are zero".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compact coordinate system parameters.
+///
+/// Compact mode is not parsed yet; only the drawing size is carried so
+/// consumers have something other than a magic fallback to work with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CompactCoordinateParams {
-    // TODO: Implement when compact coordinate mode is supported
+    /// Drawing width in pixels.
+    pub width: u16,
+    /// Drawing height in pixels.
+    pub height: u16,
+}
+
+impl Default for CompactCoordinateParams {
+    fn default() -> Self {
+        Self { width: 100, height: 100 }
+    }
 }
 
 /// Animation mode setting.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AnimationMode {
     /// Simple animation mode.
     Simple,
@@ -234,17 +718,210 @@ pub enum AnimationMode {
     Standard,
 }
 
+/// Element types in the order of the header element mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementType {
+    /// Local envelope element.
+    LocalEnvelope,
+    /// Polyline element.
+    Polyline,
+    /// Circular polyline element.
+    CircularPolyline,
+    /// Bezier polyline element.
+    BezierPolyline,
+    /// Simple shape element (rectangle, ellipse).
+    SimpleShape,
+    /// Reuse element.
+    Reuse,
+    /// Group element.
+    Group,
+    /// Animation element.
+    Animation,
+    /// Polygon element.
+    Polygon,
+    /// Special shape element (regular polygon, star, grid).
+    SpecialShape,
+    /// Frame element.
+    Frame,
+    /// Text element.
+    Text,
+    /// Extended element.
+    Extended,
+}
+
+impl ElementType {
+    /// Returns the element type for the given element mask index.
+    pub fn from_mask_index(index: usize) -> Option<Self> {
+        Some(match index {
+            0 => ElementType::LocalEnvelope,
+            1 => ElementType::Polyline,
+            2 => ElementType::CircularPolyline,
+            3 => ElementType::BezierPolyline,
+            4 => ElementType::SimpleShape,
+            5 => ElementType::Reuse,
+            6 => ElementType::Group,
+            7 => ElementType::Animation,
+            8 => ElementType::Polygon,
+            9 => ElementType::SpecialShape,
+            10 => ElementType::Frame,
+            11 => ElementType::Text,
+            12 => ElementType::Extended,
+            _ => return None,
+        })
+    }
+}
+
+/// A WVG capability and which parts of this build handle it.
+///
+/// Listed by `supported_features`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Feature {
+    /// The capability.
+    pub kind: FeatureKind,
+    /// Whether `WvgParser` reads it.
+    pub parser: bool,
+    /// Whether `SvgConverter` renders it.
+    pub svg: bool,
+}
+
+/// A WVG capability: an element type or a coding mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureKind {
+    /// An element type.
+    Element(ElementType),
+    /// Flat coordinate mode.
+    FlatCoordinates,
+}
+
+const fn feature(kind: FeatureKind, parser: bool, svg: bool) -> Feature {
+    Feature { kind, parser, svg }
+}
+
+/// Features handled by this build. Must match the element types
+/// `WvgParser` dispatches on and the element data `SvgConverter` writes.
+const SUPPORTED_FEATURES: &[Feature] = &[
+    feature(FeatureKind::Element(ElementType::Polyline), true, true),
+    feature(FeatureKind::Element(ElementType::CircularPolyline), true, true),
+    feature(FeatureKind::Element(ElementType::SimpleShape), true, true),
+    feature(FeatureKind::Element(ElementType::Reuse), true, true),
+    feature(FeatureKind::Element(ElementType::Group), true, true),
+    // Of the special shapes only stars are parsed; grids can be built
+    // programmatically
+    feature(FeatureKind::Element(ElementType::SpecialShape), true, true),
+    // Local envelopes can be built programmatically but are not parsed yet
+    feature(FeatureKind::Element(ElementType::LocalEnvelope), false, true),
+    feature(FeatureKind::FlatCoordinates, true, true),
+];
+
+/// Returns the features this build supports in the parser, the SVG
+/// converter, or both.
+///
+/// Features that neither handles are not listed.
+pub fn supported_features() -> &'static [Feature] {
+    SUPPORTED_FEATURES
+}
+
+/// Returns whether `WvgParser` reads elements of the given type.
+fn parser_supports(element_type: ElementType) -> bool {
+    SUPPORTED_FEATURES
+        .iter()
+        .any(|f| f.parser && f.kind == FeatureKind::Element(element_type))
+}
+
+/// Summary of the features a WVG file declares in its header.
+///
+/// Produced by `WvgParser::scan_features` without parsing element bodies.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureReport {
+    /// Whether the file is a Character Size WVG (nothing else is scanned then).
+    pub character_size: bool,
+    /// Whether the file uses compact coordinate mode.
+    pub compact_coordinates: bool,
+    /// Element types enabled in the element mask.
+    pub element_types: Vec<ElementType>,
+    /// Animation mode (if animation elements are enabled and it could be read).
+    pub animation_mode: Option<AnimationMode>,
+}
+
+impl FeatureReport {
+    /// Returns the declared features that the parser does not support.
+    pub fn unsupported_features(&self) -> Vec<UnsupportedFeature> {
+        let mut features = Vec::new();
+
+        if self.character_size {
+            features.push(UnsupportedFeature::CharacterSizeWvg);
+        }
+        if self.compact_coordinates {
+            features.push(UnsupportedFeature::CompactCoordinateMode);
+        }
+
+        for &element_type in &self.element_types {
+            if parser_supports(element_type) {
+                continue;
+            }
+            let feature = match element_type {
+                ElementType::Polyline
+                | ElementType::CircularPolyline
+                | ElementType::Reuse
+                | ElementType::Group => continue,
+                ElementType::LocalEnvelope => UnsupportedFeature::LocalEnvelope,
+                ElementType::BezierPolyline => UnsupportedFeature::BezierPolyline,
+                ElementType::SimpleShape => UnsupportedFeature::SimpleShape,
+                ElementType::Animation => match self.animation_mode {
+                    Some(AnimationMode::Standard) => UnsupportedFeature::StandardAnimation,
+                    _ => UnsupportedFeature::SimpleAnimation,
+                },
+                ElementType::Polygon => UnsupportedFeature::Polygon,
+                ElementType::SpecialShape => UnsupportedFeature::SpecialShape,
+                ElementType::Frame => UnsupportedFeature::FrameElement,
+                ElementType::Text => UnsupportedFeature::TextElement,
+                ElementType::Extended => UnsupportedFeature::ExtendedElement,
+            };
+            features.push(feature);
+        }
+
+        features
+    }
+
+    /// Returns true if the file declares only supported features.
+    pub fn is_supported(&self) -> bool {
+        self.unsupported_features().is_empty()
+    }
+}
+
 /// A WVG element.
+///
+/// Equality and hashing ignore `span`, so elements parsed with and without
+/// span tracking, or at different offsets, compare equal.
 #[derive(Debug, Clone)]
 pub struct WvgElement {
     /// Unique identifier for this element.
     pub id: String,
+    /// Start and end byte positions of the element in the input, when span
+    /// tracking is enabled. The end is exclusive and includes a partially
+    /// read final byte.
+    pub span: Option<(usize, usize)>,
     /// The element data.
     pub data: ElementData,
 }
 
+impl PartialEq for WvgElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.data == other.data
+    }
+}
+
+impl Eq for WvgElement {}
+
+impl Hash for WvgElement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+        self.data.hash(state);
+    }
+}
+
 /// Element-specific data.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ElementData {
     /// A polyline element.
     Polyline(PolylineElement),
@@ -258,10 +935,29 @@ pub enum ElementData {
     Reuse(ReuseElement),
     /// A simple shape element.
     SimpleShape(SimpleShapeElement),
+    /// A special shape element.
+    SpecialShape(SpecialShapeElement),
+    /// A local envelope element.
+    LocalEnvelope(LocalEnvelopeElement),
+}
+
+impl ElementData {
+    /// Returns the element's own attributes, or a reuse element's override
+    /// attributes. Groups have none.
+    pub fn attributes(&self) -> Option<&ElementAttributes> {
+        match self {
+            ElementData::Polyline(pl) => Some(&pl.attributes),
+            ElementData::CircularPolyline(cp) => Some(&cp.attributes),
+            ElementData::SimpleShape(ss) => Some(&ss.attributes),
+            ElementData::SpecialShape(ss) => Some(&ss.attributes),
+            ElementData::Reuse(reuse) => reuse.override_attributes.as_ref(),
+            ElementData::GroupStart(_) | ElementData::GroupEnd | ElementData::LocalEnvelope(_) => None,
+        }
+    }
 }
 
 /// A polyline element consisting of connected line segments.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PolylineElement {
     /// Element attributes.
     pub attributes: ElementAttributes,
@@ -270,7 +966,7 @@ pub struct PolylineElement {
 }
 
 /// A circular polyline element with arc segments.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CircularPolylineElement {
     /// Element attributes.
     pub attributes: ElementAttributes,
@@ -278,8 +974,33 @@ pub struct CircularPolylineElement {
     pub points: Vec<CircularPoint>,
 }
 
+impl CircularPolylineElement {
+    /// Returns this polyline mirrored horizontally about `x = 0`.
+    ///
+    /// Curve offsets are measured to the left of the direction of travel,
+    /// which a mirror turns into the right, so they are negated along with
+    /// the x coordinates. Negating only the coordinates would bend every arc
+    /// to the other side.
+    ///
+    /// `i32::MIN` has no positive counterpart and maps to `i32::MAX`.
+    pub fn mirrored_x(&self) -> Self {
+        Self {
+            attributes: self.attributes.clone(),
+            points: self
+                .points
+                .iter()
+                .map(|pt| CircularPoint {
+                    curve_offset: pt.curve_offset.saturating_neg(),
+                    point: Point::new(pt.point.x.saturating_neg(), pt.point.y),
+                    is_absolute: pt.is_absolute,
+                })
+                .collect(),
+        }
+    }
+}
+
 /// A point in a circular polyline.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CircularPoint {
     /// The curve offset for the arc to this point (0 = straight line).
     pub curve_offset: i32,
@@ -290,7 +1011,7 @@ pub struct CircularPoint {
 }
 
 /// A 2D point.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
@@ -301,10 +1022,15 @@ impl Point {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    /// Returns this point moved by the given offset, or `None` on overflow.
+    pub fn checked_offset(&self, dx: i32, dy: i32) -> Option<Self> {
+        Some(Self::new(self.x.checked_add(dx)?, self.y.checked_add(dy)?))
+    }
 }
 
 /// Element attributes.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct ElementAttributes {
     /// Line type (solid, dash, dotted).
     pub line_type: Option<LineType>,
@@ -318,8 +1044,21 @@ pub struct ElementAttributes {
     pub fill_color: Option<Color>,
 }
 
+impl ElementAttributes {
+    /// Returns these attributes with unset fields taken from `fallback`.
+    pub fn or(&self, fallback: &ElementAttributes) -> ElementAttributes {
+        ElementAttributes {
+            line_type: self.line_type.or(fallback.line_type),
+            line_width: self.line_width.or(fallback.line_width),
+            line_color: self.line_color.or(fallback.line_color),
+            fill: self.fill.or(fallback.fill),
+            fill_color: self.fill_color.or(fallback.fill_color),
+        }
+    }
+}
+
 /// Line type styles.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LineType {
     /// Solid line.
     Solid,
@@ -344,7 +1083,7 @@ impl From<u32> for LineType {
 }
 
 /// Line width settings.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum LineWidth {
     /// No line.
     None,
@@ -375,10 +1114,41 @@ pub struct GroupStartElement {
     pub transform: Option<Transform>,
     /// Whether the group is displayed.
     pub display: bool,
+    /// Group opacity in `0.0..=1.0`.
+    ///
+    /// Not carried by the WVG bitstream, so parsed groups have `None`; it can
+    /// be set on documents built or edited in code.
+    pub opacity: Option<f32>,
+    /// Attribute defaults inherited by the elements inside the group, with
+    /// the attributes of inner groups and of the elements taking priority.
+    ///
+    /// Parsed from the attribute set after the display bit, which is only
+    /// present when the header enables attribute masks.
+    pub attributes: ElementAttributes,
+}
+
+impl Hash for GroupStartElement {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.transform.hash(state);
+        self.display.hash(state);
+        self.opacity.map(f32::to_bits).hash(state);
+        self.attributes.hash(state);
+    }
+}
+
+impl PartialEq for GroupStartElement {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform == other.transform
+            && self.display == other.display
+            && self.opacity.map(f32::to_bits) == other.opacity.map(f32::to_bits)
+            && self.attributes == other.attributes
+    }
 }
 
+impl Eq for GroupStartElement {}
+
 /// A reuse element that references another element.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ReuseElement {
     /// Index of the element to reuse.
     pub element_index: u32,
@@ -391,7 +1161,7 @@ pub struct ReuseElement {
 }
 
 /// Array parameters for reuse elements.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ArrayParams {
     /// Number of columns.
     pub columns: u8,
@@ -404,7 +1174,7 @@ pub struct ArrayParams {
 }
 
 /// A transform operation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Transform {
     /// X translation.
     pub translate_x: Option<i32>,
@@ -423,19 +1193,452 @@ pub struct Transform {
 }
 
 /// A simple shape element (rectangle or ellipse).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SimpleShapeElement {
     /// The type of shape.
     pub shape_type: SimpleShapeType,
     /// Element attributes.
     pub attributes: ElementAttributes,
+    /// Top-left corner of the shape's bounding box, before rotation.
+    pub origin: Point,
+    /// Width of the bounding box.
+    pub width: i32,
+    /// Height of the bounding box.
+    pub height: i32,
+    /// Rotation about the shape's center, in angle resolution steps.
+    pub rotation: Option<i32>,
+}
+
+impl SimpleShapeElement {
+    /// Returns the center of the bounding box, the pivot for `rotation`.
+    pub fn center(&self) -> (f64, f64) {
+        (
+            f64::from(self.origin.x) + f64::from(self.width) / 2.0,
+            f64::from(self.origin.y) + f64::from(self.height) / 2.0,
+        )
+    }
 }
 
 /// Simple shape types.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SimpleShapeType {
     /// Rectangle shape.
     Rectangle,
     /// Ellipse shape.
     Ellipse,
 }
+
+/// A special shape element (regular polygon, star, grid).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpecialShapeElement {
+    /// The shape geometry.
+    pub shape: SpecialShape,
+    /// Element attributes.
+    pub attributes: ElementAttributes,
+}
+
+/// A local envelope element.
+///
+/// The envelope bounds the elements that follow it, up to the end of the
+/// enclosing group or of the document; content outside it is clipped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalEnvelopeElement {
+    /// Top-left corner of the envelope.
+    pub origin: Point,
+    /// Width of the envelope.
+    pub width: i32,
+    /// Height of the envelope.
+    pub height: i32,
+}
+
+/// Special shape geometries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpecialShape {
+    /// A grid of equally sized cells.
+    Grid(GridShape),
+    /// A star with alternating outer and inner vertices.
+    Star(StarShape),
+}
+
+/// A grid of `columns` x `rows` cells.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GridShape {
+    /// Top-left corner of the grid.
+    pub origin: Point,
+    /// Number of columns.
+    pub columns: u32,
+    /// Number of rows.
+    pub rows: u32,
+    /// Width of a single cell.
+    pub cell_width: i32,
+    /// Height of a single cell.
+    pub cell_height: i32,
+}
+
+/// A star with `num_points` tips on a circle of `outer_radius`, joined via
+/// vertices on a circle of `inner_radius`.
+///
+/// With equal radii the star is a regular polygon with `2 * num_points`
+/// corners.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StarShape {
+    /// Center of both circles.
+    pub center: Point,
+    /// Number of tips.
+    pub num_points: u32,
+    /// Distance from the center to each tip.
+    pub outer_radius: i32,
+    /// Distance from the center to each vertex between two tips.
+    pub inner_radius: i32,
+    /// Rotation about the center, in angle resolution steps. Unrotated
+    /// stars point straight up.
+    pub rotation: Option<i32>,
+}
+
+impl StarShape {
+    /// Returns the `2 * num_points` vertices, starting at the top tip and
+    /// alternating between tips and inner vertices clockwise.
+    ///
+    /// Stars with fewer than two tips have no vertices.
+    pub fn vertices(&self, angle_resolution: f64) -> Vec<(f64, f64)> {
+        if self.num_points < 2 {
+            return Vec::new();
+        }
+
+        let rotation = f64::from(self.rotation.unwrap_or(0)) * angle_resolution;
+        let step = std::f64::consts::PI / f64::from(self.num_points);
+        let (cx, cy) = (f64::from(self.center.x), f64::from(self.center.y));
+
+        (0..2 * self.num_points)
+            .map(|i| {
+                let radius = if i % 2 == 0 { self.outer_radius } else { self.inner_radius };
+                let angle = (rotation - 90.0).to_radians() + f64::from(i) * step;
+                let radius = f64::from(radius);
+                (cx + radius * angle.cos(), cy + radius * angle.sin())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generic_params(angle_resolution: u8, scale_resolution: u8) -> GenericParams {
+        GenericParams {
+            angle_resolution,
+            scale_resolution,
+            ..Default::default()
+        }
+    }
+
+    fn flat_params() -> FlatCoordinateParams {
+        FlatCoordinateParams {
+            drawing_width: 128,
+            drawing_height: 32,
+            max_x_in_bits: 7,
+            max_y_in_bits: 5,
+            xy_all_positive: true,
+            trans_xy_in_bits: 7,
+            num_points_in_bits: 4,
+            offset_x_in_bits_level1: 3,
+            offset_y_in_bits_level1: 3,
+            offset_x_in_bits_level2: 5,
+            offset_y_in_bits_level2: 5,
+        }
+    }
+
+    #[test]
+    fn test_coordinate_params_dimensions() {
+        assert_eq!(CoordinateParams::Flat(flat_params()).dimensions(), (128, 32));
+        assert_eq!(
+            CoordinateParams::Compact(CompactCoordinateParams::default()).dimensions(),
+            (100, 100)
+        );
+    }
+
+    #[test]
+    fn test_flat_params_valid() {
+        assert!(flat_params().validate().is_ok());
+
+        let params = FlatCoordinateParams {
+            offset_y_in_bits_level2: 6,
+            trans_xy_in_bits: 8,
+            ..flat_params()
+        };
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_flat_params_zero_coordinate_widths() {
+        let params = FlatCoordinateParams {
+            max_x_in_bits: 0,
+            max_y_in_bits: 0,
+            offset_x_in_bits_level1: 0,
+            offset_y_in_bits_level1: 0,
+            offset_x_in_bits_level2: 0,
+            offset_y_in_bits_level2: 0,
+            trans_xy_in_bits: 0,
+            ..flat_params()
+        };
+        assert!(matches!(params.validate(), Err(WvgError::InvalidCoordinateParams(_))));
+    }
+
+    #[test]
+    fn test_flat_params_wide_offsets_accepted() {
+        let params = FlatCoordinateParams {
+            offset_y_in_bits_level2: 15,
+            trans_xy_in_bits: 15,
+            ..flat_params()
+        };
+        assert!(params.validate().is_ok());
+    }
+
+    fn polyline_document(line_color: Option<Color>) -> WvgDocument {
+        WvgDocument {
+            header: WvgHeader {
+                general_info: GeneralInfo::default(),
+                color_config: ColorConfig {
+                    default_line_color: Some(Color::BLACK),
+                    ..Default::default()
+                },
+                codec_params: CodecParams {
+                    element_masks: vec![false; 8],
+                    attribute_masks: AttributeMasks::default(),
+                    generic_params: GenericParams::default(),
+                    coord_params: CoordinateParams::Flat(flat_params()),
+                },
+                animation_mode: None,
+            },
+            elements: vec![WvgElement {
+                id: "el_0".to_string(),
+                span: None,
+                data: ElementData::Polyline(PolylineElement {
+                    attributes: ElementAttributes {
+                        line_color,
+                        ..Default::default()
+                    },
+                    points: vec![Point::new(1, 2)],
+                }),
+            }],
+        }
+    }
+
+    fn line_color(element: &WvgElement) -> Option<Color> {
+        match &element.data {
+            ElementData::Polyline(pl) => pl.attributes.line_color,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_color_distance() {
+        assert_eq!(Color::BLACK.distance(&Color::BLACK), 0.0);
+        assert_eq!(Color::new(3, 4, 0).distance(&Color::BLACK), 5.0);
+        assert_eq!(Color::rgba(3, 4, 0, 0).distance(&Color::BLACK), 5.0);
+    }
+
+    #[test]
+    fn test_color_nearest() {
+        let red = Color::new(255, 0, 0);
+        let green = Color::new(0, 255, 0);
+        let palette = [Color::BLACK, Color::WHITE, red, green];
+
+        assert_eq!(Color::new(200, 30, 40).nearest(&palette), Some(&red));
+        assert_eq!(Color::new(20, 20, 20).nearest(&palette), Some(&Color::BLACK));
+        assert_eq!(Color::new(230, 240, 220).nearest(&palette), Some(&Color::WHITE));
+        assert_eq!(Color::new(10, 180, 60).nearest(&palette), Some(&green));
+        // Equidistant from red and green; the earlier entry wins
+        assert_eq!(Color::new(128, 128, 0).nearest(&[red, green]), Some(&red));
+        assert_eq!(red.nearest(&[]), None);
+    }
+
+    #[test]
+    fn test_is_animated() {
+        let mut doc = polyline_document(None);
+        assert!(!doc.is_animated());
+
+        doc.header.animation_mode = Some(AnimationMode::Simple);
+        assert!(doc.is_animated());
+    }
+
+    #[test]
+    fn test_effective_attributes_fallback() {
+        let green = Color::new(0, 255, 0);
+        let mut doc = polyline_document(None);
+        doc.header.color_config.default_fill_color = Some(green);
+
+        let attrs = doc.effective_attributes(0);
+        assert_eq!(attrs.line_color, Some(Color::BLACK));
+        assert_eq!(attrs.fill_color, Some(green));
+        assert_eq!(doc.effective_attributes(5).line_color, Some(Color::BLACK));
+    }
+
+    #[test]
+    fn test_effective_attributes_override() {
+        let red = Color::new(255, 0, 0);
+        let mut doc = polyline_document(Some(red));
+        assert_eq!(doc.effective_attributes(0).line_color, Some(red));
+
+        doc.elements.push(WvgElement {
+            id: "el_1".to_string(),
+            span: None,
+            data: ElementData::Reuse(ReuseElement {
+                element_index: 0,
+                transform: Transform::default(),
+                array_params: None,
+                override_attributes: Some(ElementAttributes {
+                    line_width: Some(LineWidth::Thick),
+                    ..Default::default()
+                }),
+            }),
+        });
+
+        let attrs = doc.effective_attributes(1);
+        assert_eq!(attrs.line_color, Some(red));
+        assert_eq!(attrs.line_width, Some(LineWidth::Thick));
+    }
+
+    #[test]
+    fn test_distinct_colors_group_inherited() {
+        let red = Color::new(255, 0, 0);
+        let mut doc = polyline_document(None);
+        let point = doc.elements[0].clone();
+        doc.elements.insert(
+            0,
+            WvgElement {
+                id: "group".to_string(),
+                span: None,
+                data: ElementData::GroupStart(GroupStartElement {
+                    transform: None,
+                    display: true,
+                    opacity: None,
+                    attributes: ElementAttributes {
+                        line_color: Some(red),
+                        ..Default::default()
+                    },
+                }),
+            },
+        );
+        doc.elements.push(WvgElement {
+            id: "group_end".to_string(),
+            span: None,
+            data: ElementData::GroupEnd,
+        });
+        doc.elements.push(point);
+
+        assert_eq!(doc.distinct_colors(), vec![red, Color::BLACK]);
+    }
+
+    #[test]
+    fn test_nesting_levels() {
+        let mut doc = polyline_document(None);
+        let point = doc.elements[0].clone();
+        let group_start = WvgElement {
+            id: "group".to_string(),
+            span: None,
+            data: ElementData::GroupStart(GroupStartElement {
+                transform: None,
+                display: true,
+                opacity: None,
+                attributes: ElementAttributes::default(),
+            }),
+        };
+        let group_end = WvgElement {
+            id: "group_end".to_string(),
+            span: None,
+            data: ElementData::GroupEnd,
+        };
+
+        // point, start, point, start, point, end, end, point, end (unmatched)
+        doc.elements = vec![
+            point.clone(),
+            group_start.clone(),
+            point.clone(),
+            group_start,
+            point.clone(),
+            group_end.clone(),
+            group_end.clone(),
+            point,
+            group_end,
+        ];
+        assert_eq!(doc.nesting_levels(), vec![0, 0, 1, 1, 2, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_replace_color() {
+        let blue = Color::new(0, 0, 255);
+        let mut doc = polyline_document(Some(Color::BLACK));
+        doc.replace_color(Color::BLACK, blue);
+
+        assert_eq!(doc.header.color_config.default_line_color, Some(blue));
+        assert_eq!(line_color(&doc.elements[0]), Some(blue));
+    }
+
+    #[test]
+    fn test_replace_color_leaves_other_colors() {
+        let red = Color::new(255, 0, 0);
+        let translucent_black = Color::rgba(0, 0, 0, 128);
+        let mut doc = polyline_document(Some(red));
+        doc.replace_color(translucent_black, Color::WHITE);
+
+        assert_eq!(doc.header.color_config.default_line_color, Some(Color::BLACK));
+        assert_eq!(line_color(&doc.elements[0]), Some(red));
+    }
+
+    #[test]
+    fn test_mirrored_x_saturates() {
+        let cp = CircularPolylineElement {
+            attributes: ElementAttributes::default(),
+            points: vec![CircularPoint {
+                curve_offset: i32::MIN,
+                point: Point::new(i32::MIN, i32::MIN),
+                is_absolute: true,
+            }],
+        };
+
+        let mirrored = cp.mirrored_x();
+        assert_eq!(mirrored.points[0].curve_offset, i32::MAX);
+        assert_eq!(mirrored.points[0].point, Point::new(i32::MAX, i32::MIN));
+    }
+
+    #[test]
+    fn test_default_resolutions() {
+        let params = GenericParams::default();
+        assert_eq!(params.angle_degrees(), 22.5);
+        assert_eq!(params.scale_step(), 0.25);
+    }
+
+    #[test]
+    fn test_angle_resolution_codes() {
+        assert_eq!(generic_params(2, 0).angle_degrees(), 11.25);
+        assert_eq!(generic_params(0, 0).angle_degrees(), 2.8125);
+    }
+
+    #[test]
+    fn test_scale_resolution_codes() {
+        assert_eq!(generic_params(3, 1).scale_step(), 0.125);
+        assert_eq!(generic_params(3, 3).scale_step(), 0.03125);
+    }
+
+    #[test]
+    fn test_supported_features() {
+        let parsed = |element_type| {
+            supported_features()
+                .iter()
+                .any(|f| f.parser && f.kind == FeatureKind::Element(element_type))
+        };
+
+        assert!(parsed(ElementType::Polyline));
+        assert!(parsed(ElementType::CircularPolyline));
+        assert!(parsed(ElementType::Reuse));
+        assert!(parsed(ElementType::Group));
+        assert!(parsed(ElementType::SimpleShape));
+        assert!(!parsed(ElementType::BezierPolyline));
+        assert!(!parsed(ElementType::Text));
+        assert!(!supported_features().iter().any(|f| matches!(
+            f.kind,
+            FeatureKind::Element(ElementType::BezierPolyline | ElementType::Text)
+        )));
+    }
+}
@@ -0,0 +1,141 @@
+//! Shared helpers for integration tests.
+
+use std::path::PathBuf;
+
+use pretty_assertions::assert_eq;
+
+/// Environment variable that makes `assert_svg_snapshot` rewrite snapshots
+/// instead of comparing against them.
+const UPDATE_SNAPSHOTS_VAR: &str = "WVG_UPDATE_SNAPSHOTS";
+
+/// Asserts that two SVG documents are equal up to insignificant differences.
+///
+/// Whitespace between tags and runs of whitespace inside text and attribute
+/// values are collapsed, attributes are compared in sorted order and
+/// `<tag />` equals `<tag/>`. On failure both documents are shown one tag
+/// per line.
+pub fn assert_svg_eq(actual: &str, expected: &str) {
+    assert_eq!(canonicalize_svg(actual), canonicalize_svg(expected));
+}
+
+/// Compares `actual` against `tests/snapshots/<name>.svg` with
+/// `assert_svg_eq`.
+///
+/// Run the tests with `WVG_UPDATE_SNAPSHOTS=1` to write `actual` to the
+/// snapshot file instead, e.g. after an intended output change.
+pub fn assert_svg_snapshot(name: &str, actual: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", &format!("{}.svg", name)]
+        .iter()
+        .collect();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read snapshot {} ({}); run with {}=1 to create it",
+            path.display(),
+            e,
+            UPDATE_SNAPSHOTS_VAR
+        )
+    });
+    assert_svg_eq(actual, &expected);
+}
+
+/// Returns `svg` with one normalized tag or text node per line.
+pub fn canonicalize_svg(svg: &str) -> String {
+    let mut lines = Vec::new();
+    let mut rest = svg;
+
+    while !rest.is_empty() {
+        match rest.find('<') {
+            Some(0) => {
+                let end = tag_end(rest).unwrap_or_else(|| panic!("unterminated tag: {}", rest));
+                lines.push(canonicalize_tag(&rest[1..end]));
+                rest = &rest[end + 1..];
+            }
+            Some(start) => {
+                push_text(&mut lines, &rest[..start]);
+                rest = &rest[start..];
+            }
+            None => {
+                push_text(&mut lines, rest);
+                rest = "";
+            }
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Returns the index of the `>` closing the tag at the start of `s`,
+/// skipping quoted attribute values.
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn push_text(lines: &mut Vec<String>, text: &str) {
+    let text = collapse_whitespace(text);
+    if !text.is_empty() {
+        lines.push(text);
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalizes the inside of a tag (without the angle brackets).
+fn canonicalize_tag(tag: &str) -> String {
+    // Declarations, processing instructions and closing tags have no
+    // attributes to reorder
+    if tag.starts_with(['?', '!', '/']) {
+        return format!("<{}>", collapse_whitespace(tag));
+    }
+
+    let (tag, self_closing) = match tag.trim_end().strip_suffix('/') {
+        Some(tag) => (tag, true),
+        None => (tag, false),
+    };
+    let tag = tag.trim();
+    let (name, mut rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        let (key, after) = rest
+            .split_once('=')
+            .unwrap_or_else(|| panic!("malformed attribute in <{}>: {}", name, rest));
+        let after = after.trim_start();
+        let quote = after.chars().next().expect("missing attribute value");
+        let len = after[1..]
+            .find(quote)
+            .unwrap_or_else(|| panic!("unterminated attribute value in <{}>", name));
+        attributes.push(format!("{}=\"{}\"", key.trim(), collapse_whitespace(&after[1..=len])));
+        rest = &after[len + 2..];
+    }
+    attributes.sort();
+
+    let mut out = format!("<{}", name);
+    for attribute in &attributes {
+        out.push(' ');
+        out.push_str(attribute);
+    }
+    out.push_str(if self_closing { "/>" } else { ">" });
+    out
+}